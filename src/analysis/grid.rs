@@ -0,0 +1,153 @@
+//! Starting grids and what drivers made of them. A grid comes from a provider that has it, see
+//! [`crate::source::DataSource::starting_grid`], or is put together from qualifying and the
+//! penalties with [`grid_from_qualifying`].
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ergast::LapTime,
+    results::{CompletionStatusCode, DriverQualifyingResult, RaceResult},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GridSlot {
+    /// `None` for a pit lane start.
+    pub position: Option<u32>,
+    pub driver_tla: String,
+    pub team_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GridPenalty {
+    Places(u32),
+    BackOfGrid,
+    PitLane,
+}
+
+/// The grid from the qualifying classification. A place penalty drops a driver behind the one
+/// that qualified that many places lower, back of the grid penalties line up behind everyone
+/// else in qualifying order and pit lane starters come last. The stewards sort out clashing
+/// penalties in more detail, a provider's grid is the real one.
+pub fn grid_from_qualifying(
+    qualifying: &[DriverQualifyingResult],
+    penalties: &[(&str, GridPenalty)],
+) -> Vec<GridSlot> {
+    let penalty = |tla: &str| {
+        penalties
+            .iter()
+            .find(|(driver, _)| *driver == tla)
+            .map(|(_, p)| *p)
+    };
+    let mut order: Vec<(u64, &DriverQualifyingResult)> = qualifying
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            // Unclassified drivers keep their order behind the classified ones.
+            let position = r
+                .position_number
+                .parse::<u64>()
+                .unwrap_or(qualifying.len() as u64 + i as u64);
+            let key = match penalty(&r.driver_tla) {
+                None => position * 2,
+                Some(GridPenalty::Places(places)) => (position + places as u64) * 2 + 1,
+                Some(GridPenalty::BackOfGrid) => (qualifying.len() as u64 * 2 + position) * 2,
+                Some(GridPenalty::PitLane) => u64::MAX,
+            };
+            (key, r)
+        })
+        .collect();
+    order.sort_by_key(|(key, _)| *key);
+    let mut position = 0;
+    order
+        .into_iter()
+        .map(|(key, r)| GridSlot {
+            position: (key != u64::MAX).then(|| {
+                position += 1;
+                position
+            }),
+            driver_tla: r.driver_tla.clone(),
+            team_name: r.team_name.clone(),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GridToFinish {
+    pub driver_tla: String,
+    pub team_name: String,
+    /// `None` for a pit lane start or a driver missing from the grid.
+    pub grid: Option<u32>,
+    /// `None` when not classified.
+    pub finish: Option<u32>,
+    /// From the grid, a pit lane start counting as last. `None` when not classified or
+    /// missing from the grid.
+    pub positions_gained: Option<i32>,
+    /// Places gained from lap to lap, pit stop shuffles included. `None` without laps or
+    /// missing from the grid.
+    pub overtakes: Option<u32>,
+    pub finished: bool,
+    /// Lap the driver retired on, the one after their last. `None` for finishers, disqualified
+    /// drivers and drivers without laps (those that didn't start among them).
+    pub retired_on_lap: Option<u32>,
+}
+
+/// Joins the grid to the race results, in the order of `race`. `laps` are optional, positions by
+/// lap like [`crate::ergast::Ergast::laps`] has them; they give the overtakes and retirement
+/// laps.
+pub fn grid_to_finish(
+    grid: &[GridSlot],
+    race: &[RaceResult],
+    laps: &[LapTime],
+) -> Vec<GridToFinish> {
+    let mut by_driver: HashMap<&str, Vec<&LapTime>> = HashMap::new();
+    for lap in laps {
+        by_driver.entry(&lap.driver_tla).or_default().push(lap);
+    }
+    for driver_laps in by_driver.values_mut() {
+        driver_laps.sort_by_key(|l| l.lap);
+    }
+    let back = grid.len() as u32;
+    race.iter()
+        .map(|r| {
+            let slot = grid.iter().find(|s| s.driver_tla == r.driver_tla);
+            let grid = slot.and_then(|s| s.position);
+            // Only pit lane starters start from the back, nothing is known of the others.
+            let start = slot.map(|s| s.position.unwrap_or(back));
+            let finish = r.position_number.parse::<u32>().ok();
+            let finished = matches!(r.completion_status_code, CompletionStatusCode::Ok);
+            let driver_laps = by_driver.get(r.driver_tla.as_str());
+            let overtakes = start.and_then(|start| {
+                let Some(driver_laps) = driver_laps else {
+                    return (!laps.is_empty()).then_some(0);
+                };
+                let mut previous = start;
+                let mut gained = 0;
+                for lap in driver_laps {
+                    gained += previous.saturating_sub(lap.position);
+                    previous = lap.position;
+                }
+                Some(gained)
+            });
+            let retired = !finished && r.position_number != "DQ";
+            let retired_on_lap = driver_laps
+                .and_then(|l| l.last())
+                .filter(|_| retired)
+                .map(|l| l.lap + 1);
+            GridToFinish {
+                driver_tla: r.driver_tla.clone(),
+                team_name: r.team_name.clone(),
+                grid,
+                finish,
+                positions_gained: start
+                    .zip(finish)
+                    .map(|(start, finish)| start as i32 - finish as i32),
+                overtakes,
+                finished,
+                retired_on_lap,
+            }
+        })
+        .collect()
+}
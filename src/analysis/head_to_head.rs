@@ -0,0 +1,171 @@
+//! Teammates against each other over any set of results, e.g. a season's qualifying, sprint
+//! and race sheets. Drivers are paired per session by team, so a mid-season driver swap makes
+//! a new pairing.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::qualifying::teammate_gaps,
+    results::{CompletionStatusCode, RaceResult, ResultSheet, SessionResult},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Teammate {
+    pub driver_tla: String,
+    /// Qualifying sessions finished ahead.
+    pub qualifying: u32,
+    /// Races finished ahead, or finished while the other didn't.
+    pub race: u32,
+    /// Race and sprint points.
+    pub points: i32,
+    /// Of the points the pair scored, 0 when they scored none.
+    pub points_share: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HeadToHead {
+    pub team_name: String,
+    /// In alphabetical order.
+    pub drivers: [Teammate; 2],
+    /// Average qualifying gap of the second driver to the first, in percent of the first's
+    /// time: positive when the first is faster. Sessions are compared in the last segment both
+    /// set a time in.
+    pub qualifying_gap_percent: Option<f64>,
+}
+
+#[derive(Default)]
+struct Tally {
+    qualifying: [u32; 2],
+    race: [u32; 2],
+    points: [i32; 2],
+    gaps: Vec<f64>,
+}
+
+type Pair = (String, [String; 2]);
+
+/// The two drivers of each team in a sheet, teams with more or fewer are left out.
+fn pairs<T>(
+    sheet: &[T],
+    team: impl Fn(&T) -> &str,
+    tla: impl Fn(&T) -> &str,
+) -> Vec<(Pair, [&T; 2])> {
+    let mut teams: Vec<(&str, Vec<&T>)> = Vec::new();
+    for result in sheet {
+        match teams.iter_mut().find(|(t, _)| *t == team(result)) {
+            Some((_, drivers)) => drivers.push(result),
+            None => teams.push((team(result), vec![result])),
+        }
+    }
+    teams
+        .into_iter()
+        .filter_map(|(team, drivers)| {
+            let [mut a, mut b] = drivers[..] else {
+                return None;
+            };
+            if tla(a) > tla(b) {
+                (a, b) = (b, a);
+            }
+            let key = (team.to_string(), [tla(a).to_string(), tla(b).to_string()]);
+            Some((key, [a, b]))
+        })
+        .collect()
+}
+
+/// Lower position wins, unclassified drivers lose to classified ones.
+fn ahead(a: &str, b: &str) -> Option<usize> {
+    match (a.parse::<u32>().ok(), b.parse::<u32>().ok()) {
+        (Some(a), Some(b)) if a != b => Some((b < a) as usize),
+        (Some(_), None) => Some(0),
+        (None, Some(_)) => Some(1),
+        _ => None,
+    }
+}
+
+/// Head-to-heads of every pairing in `results`, by team. Mutual DNFs don't count for either
+/// driver, practice sheets are ignored.
+pub fn head_to_head(results: &[SessionResult]) -> Vec<HeadToHead> {
+    let mut tallies: HashMap<Pair, Tally> = HashMap::new();
+    for result in results {
+        match &result.results {
+            ResultSheet::Qualifying(sheet) => {
+                let gaps = teammate_gaps(sheet);
+                for (key, [a, b]) in pairs(sheet, |r| &r.team_name, |r| &r.driver_tla) {
+                    let tally = tallies.entry(key).or_default();
+                    if let Some(i) = ahead(&a.position_number, &b.position_number) {
+                        tally.qualifying[i] += 1;
+                    }
+                    let gap = gaps.iter().find(|g| g.team_name == a.team_name);
+                    if let Some(gap) = gap {
+                        let sign = if gap.ahead == a.driver_tla { 1.0 } else { -1.0 };
+                        tally.gaps.push(sign * gap.gap_percent);
+                    }
+                }
+            }
+            ResultSheet::Race(sheet) => {
+                for (key, [a, b]) in pairs(sheet, |r| &r.team_name, |r| &r.driver_tla) {
+                    let tally = tallies.entry(key).or_default();
+                    let dnf = |r: &RaceResult| {
+                        matches!(r.completion_status_code, CompletionStatusCode::Dnf)
+                    };
+                    let winner = match (dnf(a), dnf(b)) {
+                        (true, true) => None,
+                        (false, true) => Some(0),
+                        (true, false) => Some(1),
+                        (false, false) => ahead(&a.position_number, &b.position_number),
+                    };
+                    if let Some(i) = winner {
+                        tally.race[i] += 1;
+                    }
+                    tally.points[0] += a.race_points;
+                    tally.points[1] += b.race_points;
+                }
+            }
+            ResultSheet::Sprint(sheet) => {
+                for (key, [a, b]) in pairs(sheet, |r| &r.team_name, |r| &r.driver_tla) {
+                    let tally = tallies.entry(key).or_default();
+                    tally.points[0] += a.sprint_points;
+                    tally.points[1] += b.sprint_points;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut head_to_heads: Vec<HeadToHead> = tallies
+        .into_iter()
+        .map(|((team_name, drivers), tally)| {
+            let total = tally.points[0] + tally.points[1];
+            let teammate = |i: usize, driver_tla: String| Teammate {
+                driver_tla,
+                qualifying: tally.qualifying[i],
+                race: tally.race[i],
+                points: tally.points[i],
+                points_share: match total {
+                    0 => 0.0,
+                    total => tally.points[i] as f64 / total as f64,
+                },
+            };
+            let [a, b] = drivers;
+            HeadToHead {
+                team_name,
+                drivers: [teammate(0, a), teammate(1, b)],
+                qualifying_gap_percent: (!tally.gaps.is_empty())
+                    .then(|| tally.gaps.iter().sum::<f64>() / tally.gaps.len() as f64),
+            }
+        })
+        .collect();
+    head_to_heads.sort_by(|a, b| {
+        let key = |h: &'_ HeadToHead| {
+            let [first, second] = &h.drivers;
+            (
+                h.team_name.clone(),
+                first.driver_tla.clone(),
+                second.driver_tla.clone(),
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+    head_to_heads
+}
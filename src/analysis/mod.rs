@@ -0,0 +1,3 @@
+pub mod grid;
+pub mod head_to_head;
+pub mod qualifying;
@@ -0,0 +1,204 @@
+//! Who went out where in qualifying, and by how much. Times are in milliseconds, margins and
+//! improvements are signed: negative improvements are faster, negative margins missed the cut.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    results::{Classifying, DriverQualifyingResult},
+    utils::parse_lap_time,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Segment {
+    Q1,
+    Q2,
+    Q3,
+}
+
+impl Segment {
+    fn of(result: &DriverQualifyingResult, segment: Segment) -> Option<&Classifying> {
+        match segment {
+            Segment::Q1 => result.q1.as_ref(),
+            Segment::Q2 => result.q2.as_ref(),
+            Segment::Q3 => result.q3.as_ref(),
+        }
+    }
+
+    fn next(self) -> Option<Segment> {
+        match self {
+            Segment::Q1 => Some(Segment::Q2),
+            Segment::Q2 => Some(Segment::Q3),
+            Segment::Q3 => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SegmentEntry {
+    pub position: u32,
+    pub driver_tla: String,
+    pub team_name: String,
+    /// `None` without a lap time.
+    pub time_ms: Option<u64>,
+    pub gap_to_fastest_ms: Option<u64>,
+    /// How much slower the driver could have been and still gone through, or by how much
+    /// they missed it. `None` in Q3 and without a time.
+    pub margin_ms: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SegmentReport {
+    pub segment: Segment,
+    /// Fastest first, drivers without a time last.
+    pub ranking: Vec<SegmentEntry>,
+    /// Drivers knocked out in this segment, none in Q3.
+    pub knocked_out: Vec<String>,
+    /// Time of the slowest driver that went through.
+    pub cut_off_ms: Option<u64>,
+}
+
+/// Teammates compared in the last segment both set a time in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TeammateGap {
+    pub team_name: String,
+    pub ahead: String,
+    pub behind: String,
+    pub segment: Segment,
+    pub gap_ms: u64,
+    pub gap_percent: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Improvement {
+    pub driver_tla: String,
+    pub from: Segment,
+    pub to: Segment,
+    pub delta_ms: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QualifyingReport {
+    /// Only the segments that were run.
+    pub segments: Vec<SegmentReport>,
+    pub teammate_gaps: Vec<TeammateGap>,
+    pub improvements: Vec<Improvement>,
+}
+
+fn time(result: &DriverQualifyingResult, segment: Segment) -> Option<u64> {
+    Segment::of(result, segment).and_then(|c| parse_lap_time(&c.classified_time))
+}
+
+fn segment_report(results: &[DriverQualifyingResult], segment: Segment) -> SegmentReport {
+    let mut drivers: Vec<&DriverQualifyingResult> = results
+        .iter()
+        .filter(|r| Segment::of(r, segment).is_some())
+        .collect();
+    drivers.sort_by_key(|r| (time(r, segment).is_none(), time(r, segment)));
+    let through = |r: &DriverQualifyingResult| match segment.next() {
+        Some(next) => Segment::of(r, next).is_some(),
+        None => true,
+    };
+    let cut_off_ms = segment.next().and_then(|_| {
+        drivers
+            .iter()
+            .filter(|r| through(r))
+            .filter_map(|r| time(r, segment))
+            .max()
+    });
+    let first_out = drivers
+        .iter()
+        .filter(|r| !through(r))
+        .filter_map(|r| time(r, segment))
+        .min();
+    let fastest = drivers.first().and_then(|r| time(r, segment));
+    let ranking = drivers
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let time_ms = time(r, segment);
+            let limit = if through(r) { first_out } else { cut_off_ms };
+            SegmentEntry {
+                position: i as u32 + 1,
+                driver_tla: r.driver_tla.clone(),
+                team_name: r.team_name.clone(),
+                time_ms,
+                gap_to_fastest_ms: time_ms.zip(fastest).map(|(t, f)| t - f),
+                margin_ms: time_ms.zip(limit).map(|(t, limit)| limit as i64 - t as i64),
+            }
+        })
+        .collect();
+    SegmentReport {
+        segment,
+        ranking,
+        knocked_out: drivers
+            .iter()
+            .filter(|r| !through(r))
+            .map(|r| r.driver_tla.clone())
+            .collect(),
+        cut_off_ms,
+    }
+}
+
+pub(crate) fn teammate_gaps(results: &[DriverQualifyingResult]) -> Vec<TeammateGap> {
+    let mut teams: Vec<(&str, Vec<&DriverQualifyingResult>)> = Vec::new();
+    for result in results {
+        match teams.iter_mut().find(|(team, _)| *team == result.team_name) {
+            Some((_, drivers)) => drivers.push(result),
+            None => teams.push((&result.team_name, vec![result])),
+        }
+    }
+    teams
+        .into_iter()
+        .filter_map(|(team, drivers)| {
+            let [a, b] = drivers[..] else {
+                return None;
+            };
+            let (segment, a_ms, b_ms) = [Segment::Q3, Segment::Q2, Segment::Q1]
+                .into_iter()
+                .find_map(|s| Some((s, time(a, s)?, time(b, s)?)))?;
+            let ((ahead, ahead_ms), (behind, behind_ms)) = if a_ms <= b_ms {
+                ((a, a_ms), (b, b_ms))
+            } else {
+                ((b, b_ms), (a, a_ms))
+            };
+            Some(TeammateGap {
+                team_name: team.to_string(),
+                ahead: ahead.driver_tla.clone(),
+                behind: behind.driver_tla.clone(),
+                segment,
+                gap_ms: behind_ms - ahead_ms,
+                gap_percent: (behind_ms - ahead_ms) as f64 / ahead_ms as f64 * 100.0,
+            })
+        })
+        .collect()
+}
+
+fn improvements(results: &[DriverQualifyingResult]) -> Vec<Improvement> {
+    let mut improvements = Vec::new();
+    for result in results {
+        for (from, to) in [(Segment::Q1, Segment::Q2), (Segment::Q2, Segment::Q3)] {
+            if let (Some(a), Some(b)) = (time(result, from), time(result, to)) {
+                improvements.push(Improvement {
+                    driver_tla: result.driver_tla.clone(),
+                    from,
+                    to,
+                    delta_ms: b as i64 - a as i64,
+                });
+            }
+        }
+    }
+    improvements
+}
+
+/// Report of a qualifying (or sprint shootout) session.
+pub fn qualifying_report(results: &[DriverQualifyingResult]) -> QualifyingReport {
+    QualifyingReport {
+        segments: [Segment::Q1, Segment::Q2, Segment::Q3]
+            .into_iter()
+            .filter(|s| results.iter().any(|r| Segment::of(r, *s).is_some()))
+            .map(|s| segment_report(results, s))
+            .collect(),
+        teammate_gaps: teammate_gaps(results),
+        improvements: improvements(results),
+    }
+}
@@ -0,0 +1,46 @@
+use std::{env, error::Error, process, time::Duration};
+
+use formula_1_library_rs::{server::serve, source::Formula1, utils::enable_cache};
+
+const USAGE: &str = "\
+Usage: f1-server [options]
+
+Options:
+    --addr <addr>         Address to listen on, defaults to 127.0.0.1:8080
+    --cache-dir <dir>     Where api responses are cached, defaults to ./f1-cache
+    --cache-max-age <s>   Seconds a cached response is reused, defaults to 300
+";
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut addr = String::from("127.0.0.1:8080");
+    let mut cache_dir = String::from("f1-cache");
+    let mut max_age = 300;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().ok_or("Missing value of `--addr`.")?,
+            "--cache-dir" => cache_dir = args.next().ok_or("Missing value of `--cache-dir`.")?,
+            "--cache-max-age" => {
+                max_age = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or("Invalid value of `--cache-max-age`.")?
+            }
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            other => return Err(format!("Unknown argument `{other}`.\n\n{USAGE}").into()),
+        }
+    }
+    enable_cache(cache_dir, Duration::from_secs(max_age))?;
+    eprintln!("Listening on http://{addr}/v1/");
+    serve(&addr, &Formula1::new(), |e| eprintln!("{e}"))
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
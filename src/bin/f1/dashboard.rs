@@ -0,0 +1,151 @@
+use std::{
+    error::Error,
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use formula_1_library_rs::telemetry::{
+    common::DataStream,
+    race_control::TrackStatus,
+    timing::{TimeColor, TimedValue, TimingState, TowerRow},
+};
+
+/// Topics the dashboard shows, everything else is left out of replays.
+pub const TOPICS: &[&str] = &[
+    "DriverList",
+    "TimingData",
+    "TimingAppData",
+    "RaceControlMessages",
+    "TrackStatus",
+    "LapCount",
+    "SessionInfo",
+];
+
+/// Redrawing on every message would flicker, the feed sends dozens per second.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+const RACE_CONTROL_LINES: usize = 8;
+
+const RESET: &str = "\x1b[0m";
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// Shows the timing tower until the stream ends. With `speed` the messages are paced by their
+/// timestamps, for replays.
+pub fn run(stream: &mut impl DataStream, speed: Option<f64>) -> Result<(), Box<dyn Error>> {
+    let mut state = TimingState::default();
+    let mut last_draw: Option<Instant> = None;
+    let mut previous: Option<DateTime<Utc>> = None;
+    while let Some(message) = stream.next_message() {
+        let message = message?;
+        if let (Some(speed), Some(previous)) = (speed, previous) {
+            if let Ok(wait) = (message.timestamp - previous).to_std() {
+                thread::sleep(wait.div_f64(speed));
+            }
+        }
+        previous = Some(message.timestamp);
+        state.apply(&message)?;
+        if last_draw.is_none_or(|t| t.elapsed() >= REDRAW_INTERVAL) {
+            draw(&state)?;
+            last_draw = Some(Instant::now());
+        }
+    }
+    draw(&state)
+}
+
+fn color(color: TimeColor) -> &'static str {
+    match color {
+        TimeColor::Purple => "\x1b[35m",
+        TimeColor::Green => "\x1b[32m",
+        TimeColor::Yellow => "\x1b[33m",
+        TimeColor::None => "",
+    }
+}
+
+/// Pads before colouring, escape codes would count towards the width otherwise.
+fn timed(value: &TimedValue, width: usize) -> String {
+    format!("{}{:<width$}{RESET}", color(value.color), value.value)
+}
+
+fn track_status(status: Option<TrackStatus>) -> String {
+    match status {
+        Some(TrackStatus::AllClear) => "\x1b[32mGREEN\x1b[0m".to_string(),
+        Some(TrackStatus::Yellow) => "\x1b[33mYELLOW\x1b[0m".to_string(),
+        Some(TrackStatus::SafetyCarDeployed) => "\x1b[33mSAFETY CAR\x1b[0m".to_string(),
+        Some(TrackStatus::VirtualSafetyCarDeployed) => "\x1b[33mVSC\x1b[0m".to_string(),
+        Some(TrackStatus::VirtualSafetyCarEnding) => "\x1b[33mVSC ENDING\x1b[0m".to_string(),
+        Some(TrackStatus::Red) => "\x1b[31mRED FLAG\x1b[0m".to_string(),
+        Some(TrackStatus::Unknown) | None => String::new(),
+    }
+}
+
+fn pit(row: &TowerRow) -> String {
+    if row.retired {
+        "OUT".to_string()
+    } else if row.in_pit {
+        "IN PIT".to_string()
+    } else if row.pit_out {
+        "PIT OUT".to_string()
+    } else {
+        format!("{} stops", row.pit_stops)
+    }
+}
+
+fn draw(state: &TimingState) -> Result<(), Box<dyn Error>> {
+    let mut out = String::from(CLEAR);
+    let lap = match state.lap_count() {
+        Some((lap, total)) => format!("Lap {lap}/{total}"),
+        None => String::new(),
+    };
+    out.push_str(&format!(
+        "{}  {lap}  {}\n\n",
+        state.session_name().unwrap_or_default(),
+        track_status(state.track_status())
+    ));
+    out.push_str(&format!(
+        "{:>3} {:<4} {:>10} {:>10}  {:<10} {:<10} {:<7} {:<7} {:<7} {:<5} {}\n",
+        "Pos", "Drv", "Gap", "Int", "Last", "Best", "S1", "S2", "S3", "Tyre", "Pit"
+    ));
+    for row in state.tower() {
+        let sector = |i: usize| {
+            row.sectors
+                .get(i)
+                .map(|s| timed(s, 7))
+                .unwrap_or_else(|| " ".repeat(7))
+        };
+        let tyre = match (row.compound, row.tyre_age) {
+            (Some(compound), Some(age)) => format!("{compound} {age}"),
+            (Some(compound), None) => compound.to_string(),
+            _ => String::new(),
+        };
+        let position = match row.position {
+            u32::MAX => String::new(),
+            position => position.to_string(),
+        };
+        out.push_str(&format!(
+            "{position:>3} {:<4} {:>10} {:>10}  {} {:<10} {} {} {} {tyre:<5} {}\n",
+            row.driver_tla,
+            row.gap_to_leader,
+            row.interval,
+            timed(&row.last_lap, 10),
+            row.best_lap,
+            sector(0),
+            sector(1),
+            sector(2),
+            pit(&row),
+        ));
+    }
+    out.push_str("\nRace control\n");
+    let messages = state.race_control_messages();
+    for message in messages.iter().rev().take(RACE_CONTROL_LINES) {
+        out.push_str(&format!(
+            "{}  {}\n",
+            message.utc.format("%H:%M:%S"),
+            message.message
+        ));
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,432 @@
+use std::{env, error::Error, path::PathBuf, process, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use formula_1_library_rs::{
+    clock::SystemClock,
+    export::{self, ColumnData},
+    notify::{Exec, Notifier, Stdout, Webhook},
+    results::{get_results, Classifying, ResultSheet},
+    schedule::{get_calendar, get_current_weekend_with, get_timetables, Timetable},
+    telemetry::{
+        live::{self, Live},
+        playback::Playback,
+        relay::Relay,
+    },
+    utils::enable_cache,
+    weekend_common::Sessions,
+};
+use serde::Serialize;
+
+mod dashboard;
+
+const USAGE: &str = "\
+Usage: f1 [options] <command>
+
+Commands:
+    calendar <year>                  Events of a season
+    weekend                          Timetable of the current or next weekend
+    timetable <meeting key>          Timetable of a weekend
+    results <meeting key> <session>  Results, session is one of
+                                     race, sprint, quali, fp1, fp2, fp3
+    live                             Live timing dashboard
+    replay <dir>                     Timing dashboard of a recorded session, a directory
+                                     of <Topic>.jsonStream files
+    relay <addr> [dir]               Serve live timing (or a recording) to websocket and
+                                     server-sent event clients on <addr>
+    notify <year>                    Print reminders before sessions of a season, when
+                                     they end and when results are in
+
+Options:
+    --format <table|json|csv>        Output format, defaults to table
+    --utc                            Show times in UTC instead of the local timezone
+    --cache-dir <dir>                Keep api responses in <dir> for an hour
+    --speed <factor>                 Replay speed, defaults to 1 (real time)
+    --before <minutes>               Remind this long before sessions, can be repeated,
+                                     defaults to 15
+    --webhook <url>                  Also POST notifications as json to <url>
+    --exec <program>                 Also run <program> for notifications, with the json
+                                     on stdin
+";
+
+/// Responses are reused for this long when `--cache-dir` is given.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+/// How often relay clients get the full state.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the notifier checks what's due.
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+struct Options {
+    format: Format,
+    utc: bool,
+    cache_dir: Option<PathBuf>,
+    speed: f64,
+    before: Vec<i64>,
+    webhook: Option<String>,
+    exec: Option<String>,
+    args: Vec<String>,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Table,
+        utc: false,
+        cache_dir: None,
+        speed: 1.0,
+        before: Vec::new(),
+        webhook: None,
+        exec: None,
+        args: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                options.format = match args.next().as_deref() {
+                    Some("table") => Format::Table,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    other => return Err(format!("Unknown format `{}`.", other.unwrap_or(""))),
+                }
+            }
+            "--utc" => options.utc = true,
+            "--cache-dir" => {
+                options.cache_dir =
+                    Some(args.next().ok_or("Missing value of `--cache-dir`.")?.into())
+            }
+            "--speed" => {
+                options.speed = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|s: &f64| *s > 0.0)
+                    .ok_or("`--speed` takes a positive number.")?
+            }
+            "--before" => options.before.push(
+                args.next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|m: &i64| *m > 0)
+                    .ok_or("`--before` takes a positive number of minutes.")?,
+            ),
+            "--webhook" => {
+                options.webhook = Some(args.next().ok_or("Missing value of `--webhook`.")?)
+            }
+            "--exec" => options.exec = Some(args.next().ok_or("Missing value of `--exec`.")?),
+            "-h" | "--help" => return Err(String::new()),
+            _ => options.args.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: Vec<&str>| {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        let mut out = vec![line(self.headers.clone())];
+        out.push(line(rule.iter().map(String::as_str).collect()));
+        for row in &self.rows {
+            out.push(line(row.iter().map(String::as_str).collect()));
+        }
+        out.join("\n")
+    }
+
+    /// Written by the library's export tables, so quoting works the same everywhere.
+    fn csv(&self) -> Result<String, Box<dyn Error>> {
+        let table = export::Table {
+            columns: self
+                .headers
+                .iter()
+                .enumerate()
+                .map(|(i, name)| export::Column {
+                    name,
+                    data: ColumnData::Utf8(self.rows.iter().map(|r| r.get(i).cloned()).collect()),
+                })
+                .collect(),
+        };
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv)?;
+        Ok(String::from_utf8(csv)?)
+    }
+}
+
+fn print<T: Serialize>(options: &Options, data: &T, table: Table) -> Result<(), Box<dyn Error>> {
+    match options.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(data)?),
+        Format::Csv => print!("{}", table.csv()?),
+        Format::Table => println!("{}", table.render()),
+    }
+    Ok(())
+}
+
+fn time(options: &Options, time: DateTime<Utc>) -> String {
+    if options.utc {
+        time.format("%Y-%m-%d %H:%M UTC").to_string()
+    } else {
+        time.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M %:z")
+            .to_string()
+    }
+}
+
+fn opt(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn timetable_table(options: &Options, timetables: &[Timetable]) -> Table {
+    Table {
+        headers: vec!["Session", "Start", "End", "State"],
+        rows: timetables
+            .iter()
+            .map(|t| {
+                vec![
+                    t.description.clone(),
+                    time(options, t.start_utc()),
+                    time(options, t.end_utc()),
+                    format!("{:?}", t.state),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn parse_session(session: &str) -> Result<Sessions, String> {
+    Ok(match session.to_lowercase().as_str() {
+        "race" | "r" => Sessions::Race,
+        "sprint" | "s" => Sessions::Sprint,
+        "quali" | "qualifying" | "q" => Sessions::Qualifying,
+        "shootout" | "ss" => return Err("Sprint shootout results aren't supported yet.".into()),
+        "fp1" | "p1" => Sessions::FreePractice1,
+        "fp2" | "p2" => Sessions::FreePractice2,
+        "fp3" | "p3" => Sessions::FreePractice3,
+        _ => return Err(format!("Unknown session `{session}`.")),
+    })
+}
+
+fn results_table(sheet: &ResultSheet) -> Table {
+    match sheet {
+        ResultSheet::Race(results) => Table {
+            headers: vec![
+                "Pos", "No", "Driver", "Team", "Time", "Gap", "Status", "Points",
+            ],
+            rows: results
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.position_number.clone(),
+                        r.racing_number.clone(),
+                        r.driver_tla.clone(),
+                        r.team_name.clone(),
+                        r.race_time.clone(),
+                        opt(&r.gap_to_leader),
+                        format!("{:?}", r.completion_status_code),
+                        r.race_points.to_string(),
+                    ]
+                })
+                .collect(),
+        },
+        ResultSheet::Sprint(results) => Table {
+            headers: vec![
+                "Pos", "No", "Driver", "Team", "Time", "Gap", "Status", "Points",
+            ],
+            rows: results
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.position_number.clone(),
+                        r.racing_number.clone(),
+                        r.driver_tla.clone(),
+                        r.team_name.clone(),
+                        r.sprint_time.clone(),
+                        opt(&r.gap_to_leader),
+                        format!("{:?}", r.completion_status_code),
+                        r.sprint_points.to_string(),
+                    ]
+                })
+                .collect(),
+        },
+        ResultSheet::Qualifying(results) => Table {
+            headers: vec!["Pos", "No", "Driver", "Team", "Q1", "Q2", "Q3"],
+            rows: results
+                .iter()
+                .map(|r| {
+                    let segment = |q: &Option<Classifying>| {
+                        q.as_ref()
+                            .map(|q| q.classified_time.clone())
+                            .unwrap_or_default()
+                    };
+                    vec![
+                        r.position_number.clone(),
+                        r.racing_number.clone(),
+                        r.driver_tla.clone(),
+                        r.team_name.clone(),
+                        segment(&r.q1),
+                        segment(&r.q2),
+                        segment(&r.q3),
+                    ]
+                })
+                .collect(),
+        },
+        ResultSheet::FreePractice1(results)
+        | ResultSheet::FreePractice2(results)
+        | ResultSheet::FreePractice3(results) => Table {
+            headers: vec!["Pos", "No", "Driver", "Team", "Time", "Gap"],
+            rows: results
+                .iter()
+                .map(|r| {
+                    vec![
+                        r.position_number.clone(),
+                        r.racing_number.clone(),
+                        r.driver_tla.clone(),
+                        r.team_name.clone(),
+                        r.classified_time.clone(),
+                        r.gap_to_leader.clone(),
+                    ]
+                })
+                .collect(),
+        },
+    }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = &options.cache_dir {
+        enable_cache(dir, CACHE_MAX_AGE)?;
+    }
+    let args: Vec<&str> = options.args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["calendar", year] => {
+            let events = get_calendar(year)?;
+            let table = Table {
+                headers: vec![
+                    "Round", "Meeting", "Location", "Country", "Start", "End", "Key",
+                ],
+                rows: events
+                    .iter()
+                    .map(|e| {
+                        vec![
+                            e.round_text.clone(),
+                            e.meeting_name.clone(),
+                            e.meeting_location.clone(),
+                            e.meeting_country_name.clone(),
+                            e.meeting_start_date.format("%Y-%m-%d").to_string(),
+                            e.meeting_end_date.format("%Y-%m-%d").to_string(),
+                            e.meeting_key.clone(),
+                        ]
+                    })
+                    .collect(),
+            };
+            print(options, &events, table)
+        }
+        ["weekend"] => {
+            let weekend = get_current_weekend_with(&SystemClock)?;
+            if options.format == Format::Table {
+                println!(
+                    "Season {}, meeting {}\n",
+                    weekend.season_year, weekend.current_or_next_meeting_key
+                );
+            }
+            print(
+                options,
+                &weekend,
+                timetable_table(options, &weekend.timetables),
+            )
+        }
+        ["timetable", meeting_key] => {
+            let timetables = get_timetables(meeting_key)?;
+            print(options, &timetables, timetable_table(options, &timetables))
+        }
+        ["results", meeting_key, session] => {
+            let result = get_results(&parse_session(session)?, meeting_key)?;
+            print(options, &result, results_table(&result.results))
+        }
+        ["live"] => dashboard::run(&mut Live::connect(dashboard::TOPICS)?, None),
+        ["replay", dir] => {
+            let mut playback =
+                Playback::from_dir_topics(dir.as_ref(), Utc::now(), dashboard::TOPICS)?;
+            dashboard::run(&mut playback, Some(options.speed))
+        }
+        ["relay", addr, rest @ ..] => {
+            let relay = Relay::bind(addr)?;
+            eprintln!("Relaying on {}", relay.local_addr());
+            let skipped = match rest {
+                [] => relay.run(&mut Live::connect(live::TOPICS)?, None, SNAPSHOT_INTERVAL)?,
+                [dir] => relay.run(
+                    &mut Playback::from_dir(dir.as_ref(), Utc::now())?,
+                    Some(options.speed),
+                    SNAPSHOT_INTERVAL,
+                )?,
+                _ => return Err(USAGE.into()),
+            };
+            if skipped > 0 {
+                eprintln!("Skipped {skipped} messages that couldn't be read.");
+            }
+            Ok(())
+        }
+        ["notify", year] => {
+            let mut notifier =
+                Notifier::from_calendar(year)?
+                    .sink(Stdout)
+                    .on_error(|notification, e| {
+                        eprintln!("Sending `{}` failed: {e}", notification.message())
+                    });
+            let before = match options.before.as_slice() {
+                [] => &[15][..],
+                before => before,
+            };
+            for minutes in before {
+                notifier = notifier.remind_before(chrono::Duration::minutes(*minutes));
+            }
+            if let Some(url) = &options.webhook {
+                notifier = notifier.sink(Webhook::new(url));
+            }
+            if let Some(program) = &options.exec {
+                notifier = notifier.sink(Exec::new(program, &[]));
+            }
+            notifier.run(NOTIFY_INTERVAL);
+            Ok(())
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) if e.is_empty() => {
+            print!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(&options) {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
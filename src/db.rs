@@ -0,0 +1,343 @@
+//! SQLite persistence, so history can be queried with SQL instead of re-downloaded. Every save
+//! is an upsert, syncing the same season twice only updates what changed upstream.
+//!
+//! Sessions are stored as `race`, `sprint`, `qualifying`, `sprint_shootout`, `practice_1`,
+//! `practice_2` or `practice_3`, times as RFC 3339 in UTC and dates as `YYYY-MM-DD`. Drivers
+//! and teams are kept per season, a driver's team is the last one they raced for that season.
+//!
+//! [`sync_season`] fills the events, sessions, results, drivers and teams. The formula1.com api
+//! has no laps or stints, those tables stay empty unless [`save_laps`] and [`save_strategy`]
+//! are called with telemetry.
+
+use std::{error::Error, fmt, path::Path};
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    results::SessionResult,
+    schedule::{Event, Timetable},
+    source::{DataSource, Formula1},
+    telemetry::{stints::RaceStrategy, trace::LapTrace},
+    weekend_common::{Sessions, Status},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    meeting_key   TEXT PRIMARY KEY,
+    season        INTEGER NOT NULL,
+    round         INTEGER,
+    name          TEXT NOT NULL,
+    official_name TEXT NOT NULL,
+    country       TEXT NOT NULL,
+    location      TEXT NOT NULL,
+    start_date    TEXT NOT NULL,
+    end_date      TEXT NOT NULL,
+    gmt_offset    TEXT NOT NULL,
+    status        TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    meeting_key TEXT NOT NULL REFERENCES events(meeting_key),
+    session     TEXT NOT NULL,
+    name        TEXT NOT NULL,
+    start_utc   TEXT NOT NULL,
+    end_utc     TEXT NOT NULL,
+    status      TEXT NOT NULL,
+    PRIMARY KEY (meeting_key, session)
+);
+CREATE TABLE IF NOT EXISTS teams (
+    season INTEGER NOT NULL,
+    name   TEXT NOT NULL,
+    colour TEXT NOT NULL,
+    PRIMARY KEY (season, name)
+);
+CREATE TABLE IF NOT EXISTS drivers (
+    season        INTEGER NOT NULL,
+    tla           TEXT NOT NULL,
+    racing_number TEXT NOT NULL,
+    first_name    TEXT NOT NULL,
+    last_name     TEXT NOT NULL,
+    team_name     TEXT NOT NULL,
+    PRIMARY KEY (season, tla),
+    FOREIGN KEY (season, team_name) REFERENCES teams(season, name)
+);
+CREATE TABLE IF NOT EXISTS results (
+    meeting_key        TEXT NOT NULL,
+    session            TEXT NOT NULL,
+    driver_tla         TEXT NOT NULL,
+    team_name          TEXT NOT NULL,
+    racing_number      TEXT NOT NULL,
+    position           INTEGER,
+    time               TEXT,
+    gap_to_leader      TEXT,
+    laps_behind_leader INTEGER,
+    finished           INTEGER,
+    points             INTEGER,
+    q1                 TEXT,
+    q2                 TEXT,
+    q3                 TEXT,
+    PRIMARY KEY (meeting_key, session, driver_tla)
+);
+CREATE TABLE IF NOT EXISTS laps (
+    meeting_key   TEXT NOT NULL,
+    session       TEXT NOT NULL,
+    racing_number TEXT NOT NULL,
+    lap_number    INTEGER NOT NULL,
+    start_utc     TEXT NOT NULL,
+    end_utc       TEXT,
+    lap_time_ms   INTEGER,
+    PRIMARY KEY (meeting_key, session, racing_number, lap_number)
+);
+CREATE TABLE IF NOT EXISTS stints (
+    meeting_key       TEXT NOT NULL,
+    session           TEXT NOT NULL,
+    racing_number     TEXT NOT NULL,
+    stint             INTEGER NOT NULL,
+    compound          TEXT NOT NULL,
+    new               INTEGER NOT NULL,
+    start_lap         INTEGER NOT NULL,
+    end_lap           INTEGER NOT NULL,
+    tyre_age_at_start INTEGER NOT NULL,
+    PRIMARY KEY (meeting_key, session, racing_number, stint)
+);
+";
+
+/// Opens (or creates) the database at `path` and makes sure the tables exist.
+pub fn open(path: &Path) -> Result<Connection, Box<dyn Error>> {
+    let conn = Connection::open(path)?;
+    create_schema(&conn)?;
+    Ok(conn)
+}
+
+pub fn create_schema(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute_batch(SCHEMA)?;
+    Ok(())
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Completed => "completed",
+        Status::Upcoming => "upcoming",
+        Status::Na => "unknown",
+    }
+}
+
+pub fn save_event(conn: &Connection, season: u32, event: &Event) -> Result<(), Box<dyn Error>> {
+    let round = event.round();
+    conn.execute(
+        "INSERT INTO events VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+         ON CONFLICT (meeting_key) DO UPDATE SET
+             season = excluded.season, round = excluded.round, name = excluded.name,
+             official_name = excluded.official_name, country = excluded.country,
+             location = excluded.location, start_date = excluded.start_date,
+             end_date = excluded.end_date, gmt_offset = excluded.gmt_offset,
+             status = excluded.status",
+        params![
+            event.meeting_key,
+            season,
+            round,
+            event.meeting_name,
+            event.meeting_official_name,
+            event.meeting_country_name,
+            event.meeting_location,
+            event.meeting_start_date.date_naive().to_string(),
+            event.meeting_end_date.date_naive().to_string(),
+            event.gmt_offset,
+            status_name(event.status),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn save_timetable(
+    conn: &Connection,
+    meeting_key: &str,
+    timetable: &Timetable,
+) -> Result<(), Box<dyn Error>> {
+    conn.execute(
+        "INSERT INTO sessions VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (meeting_key, session) DO UPDATE SET
+             name = excluded.name, start_utc = excluded.start_utc,
+             end_utc = excluded.end_utc, status = excluded.status",
+        params![
+            meeting_key,
+            timetable.session.name(),
+            timetable.description,
+            timetable.start_utc().to_rfc3339(),
+            timetable.end_utc().to_rfc3339(),
+            status_name(timetable.state),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Saves a results sheet, along with the drivers and teams in it.
+pub fn save_result(
+    conn: &Connection,
+    season: u32,
+    meeting_key: &str,
+    result: &SessionResult,
+) -> Result<(), Box<dyn Error>> {
+    let session = result.session().name();
+    for row in result.results.rows() {
+        conn.execute(
+            "INSERT INTO teams VALUES (?1, ?2, ?3)
+             ON CONFLICT (season, name) DO UPDATE SET colour = excluded.colour",
+            params![season, row.team_name, row.team_colour],
+        )?;
+        conn.execute(
+            "INSERT INTO drivers VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (season, tla) DO UPDATE SET
+                 racing_number = excluded.racing_number, first_name = excluded.first_name,
+                 last_name = excluded.last_name, team_name = excluded.team_name",
+            params![
+                season,
+                row.driver_tla,
+                row.racing_number,
+                row.first_name,
+                row.last_name,
+                row.team_name
+            ],
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO results
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                meeting_key,
+                session,
+                row.driver_tla,
+                row.team_name,
+                row.racing_number,
+                row.position,
+                row.time,
+                row.gap_to_leader,
+                row.laps_behind_leader,
+                row.finished,
+                row.points,
+                row.q1,
+                row.q2,
+                row.q3,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Saves the laps of a driver, the telemetry itself is left out.
+pub fn save_laps(
+    conn: &Connection,
+    meeting_key: &str,
+    session: Sessions,
+    racing_number: &str,
+    laps: &[LapTrace],
+) -> Result<(), Box<dyn Error>> {
+    for lap in laps {
+        conn.execute(
+            "INSERT OR REPLACE INTO laps VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                meeting_key,
+                session.name(),
+                racing_number,
+                lap.lap_number,
+                lap.start.to_rfc3339(),
+                lap.end.map(|end| end.to_rfc3339()),
+                lap.end.map(|end| (end - lap.start).num_milliseconds()),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn save_strategy(
+    conn: &Connection,
+    meeting_key: &str,
+    session: Sessions,
+    strategy: &RaceStrategy,
+) -> Result<(), Box<dyn Error>> {
+    for driver in &strategy.drivers {
+        for (i, stint) in driver.stints.iter().enumerate() {
+            // Same names as the feed, `SOFT`, `MEDIUM`, ...
+            let compound = serde_json::to_value(stint.compound)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO stints VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    meeting_key,
+                    session.name(),
+                    driver.racing_number,
+                    i as u32 + 1,
+                    compound.as_str(),
+                    stint.new,
+                    stint.start_lap,
+                    stint.end_lap,
+                    stint.tyre_age_at_start,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A session [`sync_season`] couldn't read the results of.
+#[derive(Debug)]
+pub struct SkippedSession {
+    pub meeting_key: String,
+    pub session: Sessions,
+    pub error: Box<dyn Error>,
+}
+
+impl fmt::Display for SkippedSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.meeting_key,
+            self.session.name(),
+            self.error
+        )
+    }
+}
+
+/// Downloads the calendar, timetables and results of every completed session of `year` into
+/// the database, one transaction per event. Results that can't be read don't stop the sync,
+/// they come back as [`SkippedSession`]s. Sprint shootouts are left out, their sheet isn't read
+/// (see [`crate::results::ResultSheet`]).
+pub fn sync_season(
+    conn: &mut Connection,
+    year: &str,
+) -> Result<Vec<SkippedSession>, Box<dyn Error>> {
+    sync_season_from(conn, &Formula1::new(), year)
+}
+
+/// [`sync_season`] from `source`.
+pub fn sync_season_from(
+    conn: &mut Connection,
+    source: &dyn DataSource,
+    year: &str,
+) -> Result<Vec<SkippedSession>, Box<dyn Error>> {
+    let season: u32 = year.parse()?;
+    let mut skipped = Vec::new();
+    for event in source.calendar(year)? {
+        let tx = conn.transaction()?;
+        save_event(&tx, season, &event)?;
+        // Testing has no round, timetable or results.
+        if event.round().is_some() {
+            for timetable in source.timetables(&event.meeting_key)? {
+                save_timetable(&tx, &event.meeting_key, &timetable)?;
+                if timetable.state != Status::Completed
+                    || timetable.session == Sessions::SprintShootout
+                {
+                    continue;
+                }
+                match source.results(&timetable.session, &event.meeting_key) {
+                    Ok(result) => save_result(&tx, season, &event.meeting_key, &result)?,
+                    Err(error) => skipped.push(SkippedSession {
+                        meeting_key: event.meeting_key.clone(),
+                        session: timetable.session,
+                        error,
+                    }),
+                }
+            }
+        }
+        tx.commit()?;
+    }
+    Ok(skipped)
+}
@@ -0,0 +1,793 @@
+//! Backend for the Ergast api, as continued by Jolpica. Covers every season since 1950, but has
+//! no practice results and no live data.
+//!
+//! Meeting keys are `<season>-<round>`, like `2023-10`. Ergast has times in UTC only, so
+//! everything is mapped with a `gmtOffset` of `+00:00`.
+
+use std::{collections::HashMap, error::Error, sync::Arc};
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    analysis::grid::GridSlot,
+    clock::{Clock, SystemClock},
+    results::{
+        Classifying, CompletionStatusCode, DriverQualifyingResult, RaceResult, ResultSheet,
+        SessionResult, SprintResult,
+    },
+    schedule::{Event, Timetable},
+    source::DataSource,
+    standings::{ConstructorStanding, DriverStanding, Standings},
+    telemetry::stints::PitStop,
+    utils::{get_text, parse_lap_time},
+    weekend_common::{whole_points, Sessions, Status},
+};
+
+pub const JOLPICA_URL: &str = "https://api.jolpi.ca/ergast/f1/";
+
+/// Jolpica doesn't hand out pages larger than this.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionTime {
+    pub date: String,
+    pub time: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Location {
+    pub locality: String,
+    pub country: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Circuit {
+    #[serde(rename = "circuitId")]
+    pub circuit_id: String,
+    #[serde(rename = "circuitName")]
+    pub circuit_name: String,
+    #[serde(rename = "Location")]
+    pub location: Location,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Race {
+    pub season: String,
+    pub round: String,
+    #[serde(rename = "raceName")]
+    pub race_name: String,
+    #[serde(rename = "Circuit")]
+    pub circuit: Circuit,
+    pub date: String,
+    pub time: Option<String>,
+    #[serde(rename = "FirstPractice")]
+    pub first_practice: Option<SessionTime>,
+    #[serde(rename = "SecondPractice")]
+    pub second_practice: Option<SessionTime>,
+    #[serde(rename = "ThirdPractice")]
+    pub third_practice: Option<SessionTime>,
+    #[serde(rename = "Qualifying")]
+    pub qualifying: Option<SessionTime>,
+    #[serde(rename = "Sprint")]
+    pub sprint: Option<SessionTime>,
+    /// 2023 name of the sprint qualifying.
+    #[serde(rename = "SprintShootout")]
+    pub sprint_shootout: Option<SessionTime>,
+    #[serde(rename = "SprintQualifying")]
+    pub sprint_qualifying: Option<SessionTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Driver {
+    #[serde(rename = "driverId")]
+    pub driver_id: String,
+    pub code: Option<String>,
+    #[serde(rename = "permanentNumber")]
+    pub permanent_number: Option<String>,
+    #[serde(rename = "givenName")]
+    pub given_name: String,
+    #[serde(rename = "familyName")]
+    pub family_name: String,
+}
+
+impl Driver {
+    /// Drivers from before 2000 mostly don't have a code, those get the start of their name.
+    pub fn tla(&self) -> String {
+        match &self.code {
+            Some(code) => code.clone(),
+            None => self
+                .family_name
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .take(3)
+                .collect::<String>()
+                .to_uppercase(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Constructor {
+    #[serde(rename = "constructorId")]
+    pub constructor_id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResultTime {
+    pub time: String,
+}
+
+/// A line of race, sprint or qualifying results, the fields of the other kinds are `None`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ErgastResult {
+    pub number: String,
+    pub position: String,
+    #[serde(rename = "positionText")]
+    pub position_text: Option<String>,
+    pub points: Option<String>,
+    #[serde(rename = "Driver")]
+    pub driver: Driver,
+    #[serde(rename = "Constructor")]
+    pub constructor: Constructor,
+    pub grid: Option<String>,
+    pub laps: Option<String>,
+    pub status: Option<String>,
+    #[serde(rename = "Time")]
+    pub time: Option<ResultTime>,
+    #[serde(rename = "Q1")]
+    pub q1: Option<String>,
+    #[serde(rename = "Q2")]
+    pub q2: Option<String>,
+    #[serde(rename = "Q3")]
+    pub q3: Option<String>,
+}
+
+impl ErgastResult {
+    /// `positionText` is a letter for drivers that weren't classified, `R`etired, `D`isqualified
+    /// and so on.
+    fn position_number(&self) -> String {
+        match self.position_text.as_deref() {
+            Some(text) if text.parse::<u32>().is_ok() => text.to_string(),
+            Some("D") => "DQ".to_string(),
+            Some(_) => "NC".to_string(),
+            None => self.position.clone(),
+        }
+    }
+
+    /// `Finished` and `+1 Lap` are classified finishes, anything else (`Accident`, `Engine`,
+    /// ...) is not.
+    fn completion_status_code(&self) -> CompletionStatusCode {
+        match self.status.as_deref() {
+            Some("Finished") => CompletionStatusCode::Ok,
+            Some(status) if status.starts_with('+') => CompletionStatusCode::Ok,
+            _ => CompletionStatusCode::Dnf,
+        }
+    }
+
+    fn laps_behind_leader(&self) -> Option<String> {
+        let status = self.status.as_deref()?.strip_prefix('+')?;
+        Some(status.split_whitespace().next()?.to_string())
+    }
+
+    /// The winner has the race time, everyone else a `+12.345` gap.
+    fn gap_to_leader(&self) -> Option<String> {
+        self.time
+            .as_ref()
+            .map(|t| t.time.clone())
+            .filter(|t| t.starts_with('+'))
+    }
+
+    fn race_time(&self) -> String {
+        match &self.time {
+            Some(time) => time.time.clone(),
+            None => self.status.clone().unwrap_or_default(),
+        }
+    }
+
+    fn points(&self) -> i32 {
+        self.points.as_deref().map_or(0, parse_points)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct DriverStandingEntry {
+    position: Option<String>,
+    points: String,
+    wins: String,
+    #[serde(rename = "Driver")]
+    driver: Driver,
+    #[serde(rename = "Constructors")]
+    constructors: Vec<Constructor>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ConstructorStandingEntry {
+    position: Option<String>,
+    points: String,
+    wins: String,
+    #[serde(rename = "Constructor")]
+    constructor: Constructor,
+}
+
+/// Time of one lap of one driver.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LapTime {
+    pub driver_tla: String,
+    pub lap: u32,
+    pub position: u32,
+    pub time_ms: u64,
+}
+
+fn parse_datetime(date: &str, time: Option<&str>) -> Result<DateTime<Utc>, Box<dyn Error>> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+    // Old races have no start time.
+    let time = match time {
+        Some(time) => NaiveTime::parse_from_str(time.trim_end_matches('Z'), "%H:%M:%S")?,
+        None => NaiveTime::MIN,
+    };
+    Ok(date.and_time(time).and_utc())
+}
+
+/// `2023-10` to `("2023", "10")`.
+fn split_meeting_key(meeting_key: &str) -> Result<(&str, &str), Box<dyn Error>> {
+    meeting_key.split_once('-').ok_or_else(|| {
+        format!("`{meeting_key}` is not an Ergast meeting key, like `2023-10`.").into()
+    })
+}
+
+/// Ergast has points as text, see [`whole_points`] for the half points.
+fn parse_points(points: &str) -> i32 {
+    points.parse().map_or(0, whole_points)
+}
+
+impl Race {
+    /// The sessions of the weekend with their (rough) length, Ergast only has start times.
+    /// Sessions that ended by `now` are completed.
+    pub fn timetables(&self, now: DateTime<Utc>) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        let sessions = [
+            (
+                Sessions::FreePractice1,
+                "Practice 1",
+                &self.first_practice,
+                1,
+            ),
+            (
+                Sessions::FreePractice2,
+                "Practice 2",
+                &self.second_practice,
+                1,
+            ),
+            (
+                Sessions::FreePractice3,
+                "Practice 3",
+                &self.third_practice,
+                1,
+            ),
+            (
+                Sessions::SprintShootout,
+                "Sprint Shootout",
+                &self.sprint_shootout,
+                1,
+            ),
+            (
+                Sessions::SprintShootout,
+                "Sprint Qualifying",
+                &self.sprint_qualifying,
+                1,
+            ),
+            (Sessions::Qualifying, "Qualifying", &self.qualifying, 1),
+            (Sessions::Sprint, "Sprint", &self.sprint, 1),
+        ];
+        let mut timetables = Vec::new();
+        for (session, description, time, hours) in sessions {
+            if let Some(time) = time {
+                let start = parse_datetime(&time.date, time.time.as_deref())?;
+                timetables.push(timetable(session, description, start, hours, now));
+            }
+        }
+        timetables.push(timetable(
+            Sessions::Race,
+            &self.race_name,
+            self.start()?,
+            2,
+            now,
+        ));
+        timetables.sort_by_key(|t| t.start_date);
+        Ok(timetables)
+    }
+
+    pub fn start(&self) -> Result<DateTime<Utc>, Box<dyn Error>> {
+        parse_datetime(&self.date, self.time.as_deref())
+    }
+
+    pub fn to_event(&self, now: DateTime<Utc>) -> Result<Event, Box<dyn Error>> {
+        let timetables = self.timetables(now)?;
+        let race = timetables.last().ok_or("Race without sessions.")?;
+        Ok(Event {
+            circuit_medium_image_url: String::new(),
+            meeting_country_name: self.circuit.location.country.clone(),
+            meeting_location: self.circuit.location.locality.clone(),
+            meeting_official_name: self.race_name.clone(),
+            meeting_start_date: timetables[0].start_date,
+            meeting_end_date: race.end_date,
+            meeting_key: format!("{}-{}", self.season, self.round),
+            meeting_name: self.race_name.clone(),
+            gmt_offset: "+00:00".to_string(),
+            status: race.state,
+            round_text: format!("ROUND {}", self.round),
+        })
+    }
+}
+
+fn timetable(
+    session: Sessions,
+    description: &str,
+    start: DateTime<Utc>,
+    hours: i64,
+    now: DateTime<Utc>,
+) -> Timetable {
+    let end = start + Duration::hours(hours);
+    Timetable {
+        state: if end <= now {
+            Status::Completed
+        } else {
+            Status::Upcoming
+        },
+        session,
+        description: description.to_string(),
+        start_date: start,
+        end_date: end,
+        gmt_offset: "+00:00".to_string(),
+    }
+}
+
+/// Client of an Ergast compatible api, Jolpica by default. Responses go through the cache of
+/// [`crate::utils::enable_cache`].
+#[derive(Clone)]
+pub struct Ergast {
+    base_url: String,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl std::fmt::Debug for Ergast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ergast")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Ergast {
+    fn default() -> Self {
+        Ergast::with_base_url(JOLPICA_URL)
+    }
+}
+
+impl Ergast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// For mirrors or a self hosted instance, `base_url` is the part before `2023.json`.
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Ergast {
+            base_url,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// What tells completed sessions from upcoming ones, the system clock by default.
+    pub fn clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Every page of `path` (like `2023/10/laps`), the `MRData` of each.
+    fn pages(&self, path: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+        let mut pages = Vec::new();
+        let mut offset = 0;
+        loop {
+            let url = format!(
+                "{}{path}.json?limit={PAGE_SIZE}&offset={offset}",
+                self.base_url
+            );
+            let mut page: Value = serde_json::from_str(&get_text(&url)?)?;
+            let data = page
+                .get_mut("MRData")
+                .ok_or("Key `MRData` not in received json.")?
+                .take();
+            let total: usize = data
+                .get("total")
+                .and_then(Value::as_str)
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0);
+            pages.push(data);
+            offset += PAGE_SIZE;
+            if offset >= total {
+                return Ok(pages);
+            }
+        }
+    }
+
+    /// The races of `path`. Pages count the items of `list` (like `Laps`) when given, a race
+    /// split over pages is joined again.
+    fn races(&self, path: &str, list: Option<&str>) -> Result<Vec<Value>, Box<dyn Error>> {
+        let mut races: Vec<Value> = Vec::new();
+        for mut page in self.pages(path)? {
+            let Some(Value::Array(page_races)) = page
+                .get_mut("RaceTable")
+                .and_then(|t| t.get_mut("Races"))
+                .map(Value::take)
+            else {
+                return Err("Key `RaceTable.Races` not in received json.".into());
+            };
+            for mut race in page_races {
+                let same = races.last().is_some_and(|last| {
+                    last.get("season") == race.get("season")
+                        && last.get("round") == race.get("round")
+                });
+                let existing = races
+                    .last_mut()
+                    .filter(|_| same)
+                    .zip(list)
+                    .and_then(|(last, list)| last.get_mut(list));
+                match (existing, list.and_then(|list| race.get_mut(list))) {
+                    (Some(Value::Array(existing)), Some(Value::Array(items))) => {
+                        existing.append(items)
+                    }
+                    _ => races.push(race),
+                }
+            }
+        }
+        Ok(races)
+    }
+
+    /// `list` of the single race at `path`.
+    fn race_list<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        list: &str,
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let mut races = self.races(path, Some(list))?;
+        let race = races
+            .first_mut()
+            .ok_or_else(|| format!("No race at `{path}`."))?;
+        let items = race
+            .get_mut(list)
+            .ok_or_else(|| format!("Key `{list}` not in received json."))?
+            .take();
+        Ok(serde_json::from_value(items)?)
+    }
+
+    pub fn seasons(&self) -> Result<Vec<u32>, Box<dyn Error>> {
+        let mut seasons = Vec::new();
+        for page in self.pages("seasons")? {
+            for season in page
+                .get("SeasonTable")
+                .and_then(|t| t.get("Seasons"))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(year) = season.get("season").and_then(Value::as_str) {
+                    seasons.push(year.parse()?);
+                }
+            }
+        }
+        Ok(seasons)
+    }
+
+    pub fn schedule(&self, year: &str) -> Result<Vec<Race>, Box<dyn Error>> {
+        let races = self.races(year, None)?;
+        Ok(serde_json::from_value(Value::Array(races))?)
+    }
+
+    pub fn race(&self, season: &str, round: &str) -> Result<Race, Box<dyn Error>> {
+        let races = self.races(&format!("{season}/{round}"), None)?;
+        let race = races
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No race {season} round {round}."))?;
+        Ok(serde_json::from_value(race)?)
+    }
+
+    pub fn race_results(
+        &self,
+        season: &str,
+        round: &str,
+    ) -> Result<Vec<ErgastResult>, Box<dyn Error>> {
+        self.race_list(&format!("{season}/{round}/results"), "Results")
+    }
+
+    pub fn sprint_results(
+        &self,
+        season: &str,
+        round: &str,
+    ) -> Result<Vec<ErgastResult>, Box<dyn Error>> {
+        self.race_list(&format!("{season}/{round}/sprint"), "SprintResults")
+    }
+
+    pub fn qualifying_results(
+        &self,
+        season: &str,
+        round: &str,
+    ) -> Result<Vec<ErgastResult>, Box<dyn Error>> {
+        self.race_list(&format!("{season}/{round}/qualifying"), "QualifyingResults")
+    }
+
+    /// `driverId` to TLA, pit stops and laps only have the id.
+    fn driver_codes(
+        &self,
+        season: &str,
+        round: &str,
+    ) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        Ok(self
+            .race_results(season, round)?
+            .into_iter()
+            .map(|r| (r.driver.driver_id.clone(), r.driver.tla()))
+            .collect())
+    }
+
+    /// Pit stops by driver TLA, Ergast has them from 2012 on.
+    pub fn pit_stops(
+        &self,
+        season: &str,
+        round: &str,
+    ) -> Result<HashMap<String, Vec<PitStop>>, Box<dyn Error>> {
+        let codes = self.driver_codes(season, round)?;
+        let stops: Vec<Value> =
+            self.race_list(&format!("{season}/{round}/pitstops"), "PitStops")?;
+        let mut pit_stops: HashMap<String, Vec<PitStop>> = HashMap::new();
+        for stop in stops {
+            let text = |key| stop.get(key).and_then(Value::as_str).unwrap_or_default();
+            let driver = codes
+                .get(text("driverId"))
+                .cloned()
+                .unwrap_or_else(|| text("driverId").to_string());
+            let lap: u32 = text("lap").parse()?;
+            pit_stops.entry(driver).or_default().push(PitStop {
+                in_lap: lap,
+                out_lap: lap + 1,
+                pit_lane_time: parse_lap_time(text("duration")).map(|ms| ms as f64 / 1000.0),
+                stationary_time: None,
+            });
+        }
+        Ok(pit_stops)
+    }
+
+    /// Every lap of every driver, Ergast has them from 1996 on.
+    pub fn laps(&self, season: &str, round: &str) -> Result<Vec<LapTime>, Box<dyn Error>> {
+        let codes = self.driver_codes(season, round)?;
+        let laps: Vec<Value> = self.race_list(&format!("{season}/{round}/laps"), "Laps")?;
+        let mut lap_times = Vec::new();
+        for lap in laps {
+            let number: u32 = lap
+                .get("number")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .parse()?;
+            for timing in lap
+                .get("Timings")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let text = |key| timing.get(key).and_then(Value::as_str).unwrap_or_default();
+                let Some(time_ms) = parse_lap_time(text("time")) else {
+                    continue;
+                };
+                lap_times.push(LapTime {
+                    driver_tla: codes
+                        .get(text("driverId"))
+                        .cloned()
+                        .unwrap_or_else(|| text("driverId").to_string()),
+                    lap: number,
+                    position: text("position").parse().unwrap_or(0),
+                    time_ms,
+                });
+            }
+        }
+        Ok(lap_times)
+    }
+}
+
+impl DataSource for Ergast {
+    fn name(&self) -> &str {
+        "ergast"
+    }
+
+    fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+        let now = self.clock.now();
+        self.schedule(year)?
+            .iter()
+            .map(|race| race.to_event(now))
+            .collect()
+    }
+
+    fn timetables(&self, meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        let (season, round) = split_meeting_key(meeting_key)?;
+        self.race(season, round)?.timetables(self.clock.now())
+    }
+
+    fn results(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<SessionResult, Box<dyn Error>> {
+        let (season, round) = split_meeting_key(meeting_key)?;
+        let results = match session {
+            Sessions::Race => ResultSheet::Race(
+                self.race_results(season, round)?
+                    .iter()
+                    .map(|r| RaceResult {
+                        team_colour_code: String::new(),
+                        driver_tla: r.driver.tla(),
+                        team_name: r.constructor.name.clone(),
+                        racing_number: r.number.clone(),
+                        position_number: r.position_number(),
+                        driver_first_name: r.driver.given_name.clone(),
+                        driver_last_name: r.driver.family_name.clone(),
+                        driver_name_format: String::new(),
+                        gap_to_previous: None,
+                        gap_to_leader: r.gap_to_leader(),
+                        completion_status_code: r.completion_status_code(),
+                        race_time: r.race_time(),
+                        driver_reference: r.driver.driver_id.clone(),
+                        laps_behind_leader: r.laps_behind_leader(),
+                        race_points: r.points(),
+                        driver_image_url: String::new(),
+                    })
+                    .collect(),
+            ),
+            Sessions::Sprint => ResultSheet::Sprint(
+                self.sprint_results(season, round)?
+                    .iter()
+                    .map(|r| SprintResult {
+                        team_colour_code: String::new(),
+                        driver_tla: r.driver.tla(),
+                        team_name: r.constructor.name.clone(),
+                        racing_number: r.number.clone(),
+                        position_number: r.position_number(),
+                        driver_first_name: r.driver.given_name.clone(),
+                        driver_last_name: r.driver.family_name.clone(),
+                        driver_name_format: String::new(),
+                        gap_to_previous: None,
+                        gap_to_leader: r.gap_to_leader(),
+                        completion_status_code: r.completion_status_code(),
+                        sprint_time: r.race_time(),
+                        driver_reference: r.driver.driver_id.clone(),
+                        laps_behind_leader: r.laps_behind_leader(),
+                        sprint_points: r.points(),
+                        driver_image_url: String::new(),
+                    })
+                    .collect(),
+            ),
+            Sessions::Qualifying => {
+                let segment = |time: &Option<String>| {
+                    time.as_ref()
+                        .filter(|t| !t.is_empty())
+                        .map(|t| Classifying {
+                            classified_time: t.clone(),
+                            completion_status_code: CompletionStatusCode::Ok,
+                        })
+                };
+                ResultSheet::Qualifying(
+                    self.qualifying_results(season, round)?
+                        .iter()
+                        .map(|r| DriverQualifyingResult {
+                            q1: segment(&r.q1),
+                            q2: segment(&r.q2),
+                            q3: segment(&r.q3),
+                            team_colour_code: String::new(),
+                            driver_tla: r.driver.tla(),
+                            team_name: r.constructor.name.clone(),
+                            racing_number: r.number.clone(),
+                            position_number: r.position.clone(),
+                            driver_first_name: r.driver.given_name.clone(),
+                            driver_last_name: r.driver.family_name.clone(),
+                            driver_name_format: String::new(),
+                        })
+                        .collect(),
+                )
+            }
+            other => return Err(format!("Ergast has no {} results.", other.name()).into()),
+        };
+        Ok(SessionResult {
+            state: Status::Completed,
+            session_info: None,
+            results,
+        })
+    }
+
+    fn standings(&self, year: &str) -> Result<Standings, Box<dyn Error>> {
+        // Seasons with the Indianapolis 500 have more drivers than fit a page.
+        let list = |path: &str, key: &str| -> Result<Value, Box<dyn Error>> {
+            let mut entries = Vec::new();
+            for mut page in self.pages(path)? {
+                if let Some(Value::Array(items)) = page
+                    .pointer_mut(&format!("/StandingsTable/StandingsLists/0/{key}"))
+                    .map(Value::take)
+                {
+                    entries.extend(items);
+                }
+            }
+            Ok(Value::Array(entries))
+        };
+        let drivers: Vec<DriverStandingEntry> =
+            serde_json::from_value(list(&format!("{year}/driverStandings"), "DriverStandings")?)?;
+        let constructors: Vec<ConstructorStandingEntry> = serde_json::from_value(list(
+            &format!("{year}/constructorStandings"),
+            "ConstructorStandings",
+        )?)?;
+        let position = |position: &Option<String>, i: usize| {
+            position
+                .as_deref()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(i as u32 + 1)
+        };
+        Ok(Standings {
+            drivers: drivers
+                .iter()
+                .enumerate()
+                .map(|(i, d)| DriverStanding {
+                    position: position(&d.position, i),
+                    driver_tla: d.driver.tla(),
+                    first_name: d.driver.given_name.clone(),
+                    last_name: d.driver.family_name.clone(),
+                    team_name: d
+                        .constructors
+                        .last()
+                        .map(|c| c.name.clone())
+                        .unwrap_or_default(),
+                    points: parse_points(&d.points),
+                    wins: d.wins.parse().unwrap_or(0),
+                })
+                .collect(),
+            constructors: constructors
+                .iter()
+                .enumerate()
+                .map(|(i, c)| ConstructorStanding {
+                    position: position(&c.position, i),
+                    team_name: c.constructor.name.clone(),
+                    points: parse_points(&c.points),
+                    wins: c.wins.parse().unwrap_or(0),
+                })
+                .collect(),
+        })
+    }
+
+    /// From the `grid` of race or sprint results, where `0` is a pit lane start.
+    fn starting_grid(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<GridSlot>, Box<dyn Error>> {
+        let (season, round) = split_meeting_key(meeting_key)?;
+        let results = match session {
+            Sessions::Race => self.race_results(season, round)?,
+            Sessions::Sprint => self.sprint_results(season, round)?,
+            other => return Err(format!("Ergast has no {} grid.", other.name()).into()),
+        };
+        let mut grid: Vec<GridSlot> = results
+            .iter()
+            .map(|r| GridSlot {
+                position: r
+                    .grid
+                    .as_deref()
+                    .and_then(|g| g.parse().ok())
+                    .filter(|g| *g > 0),
+                driver_tla: r.driver.tla(),
+                team_name: r.constructor.name.clone(),
+            })
+            .collect();
+        grid.sort_by_key(|s| (s.position.is_none(), s.position));
+        Ok(grid)
+    }
+}
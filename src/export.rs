@@ -0,0 +1,329 @@
+//! Flat tables for pandas, polars and friends, written as CSV or (with the `arrow` feature) as
+//! Arrow IPC or Parquet. Every column is nullable, column names and types don't change between
+//! sessions, so files of different sessions can be concatenated.
+//!
+//! [`results_table`], one row per driver:
+//!
+//! | Column               | Type      |                                   |
+//! |----------------------|-----------|-----------------------------------|
+//! | `session`            | utf8      | `race`, `qualifying`, ...         |
+//! | `position`           | uint32    | `null` when not classified        |
+//! | `racing_number`      | utf8      |                                   |
+//! | `driver_tla`         | utf8      |                                   |
+//! | `first_name`         | utf8      |                                   |
+//! | `last_name`          | utf8      |                                   |
+//! | `team_name`          | utf8      |                                   |
+//! | `team_colour`        | utf8      | hex, without `#`                  |
+//! | `time`               | utf8      | race time or fastest lap          |
+//! | `gap_to_leader`      | utf8      |                                   |
+//! | `laps_behind_leader` | uint32    |                                   |
+//! | `finished`           | boolean   | race and sprint                   |
+//! | `points`             | int32     | race and sprint                   |
+//! | `q1`, `q2`, `q3`     | utf8      | qualifying                        |
+//!
+//! [`laps_table`], one row per lap:
+//!
+//! | Column          | Type      |                                        |
+//! |-----------------|-----------|----------------------------------------|
+//! | `racing_number` | utf8      |                                        |
+//! | `lap_number`    | uint32    |                                        |
+//! | `start`         | timestamp | ms, UTC                                |
+//! | `end`           | timestamp | `null` for a lap that wasn't finished  |
+//! | `lap_time_ms`   | int64     |                                        |
+//!
+//! [`telemetry_table`], one row per sample, see [`TelemetryPoint`]:
+//! `racing_number` (utf8), `lap_number` (uint32), `utc` (timestamp), `distance` (float64, m),
+//! `x`, `y`, `z` (float64, 1/10 m), `speed` (float64, km/h), `throttle` (float64, %), `brake`
+//! (boolean), `gear` (uint32), `rpm` (float64) and `drs` (boolean).
+
+use std::{error::Error, io::Write};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    results::{ResultRow, SessionResult},
+    telemetry::trace::{LapTrace, TelemetryPoint},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnData {
+    Utf8(Vec<Option<String>>),
+    UInt32(Vec<Option<u32>>),
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    Float64(Vec<Option<f64>>),
+    Boolean(Vec<Option<bool>>),
+    /// Written with millisecond precision.
+    Timestamp(Vec<Option<DateTime<Utc>>>),
+}
+
+impl ColumnData {
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnData::Utf8(v) => v.len(),
+            ColumnData::UInt32(v) => v.len(),
+            ColumnData::Int32(v) => v.len(),
+            ColumnData::Int64(v) => v.len(),
+            ColumnData::Float64(v) => v.len(),
+            ColumnData::Boolean(v) => v.len(),
+            ColumnData::Timestamp(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of `row` as a CSV field, empty for `null`.
+    fn csv_field(&self, row: usize) -> String {
+        fn text<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+        match self {
+            ColumnData::Utf8(v) => match &v[row] {
+                Some(s) if s.contains([',', '"', '\n', '\r']) => {
+                    format!("\"{}\"", s.replace('"', "\"\""))
+                }
+                s => text(s),
+            },
+            ColumnData::UInt32(v) => text(&v[row]),
+            ColumnData::Int32(v) => text(&v[row]),
+            ColumnData::Int64(v) => text(&v[row]),
+            ColumnData::Float64(v) => text(&v[row]),
+            ColumnData::Boolean(v) => text(&v[row]),
+            ColumnData::Timestamp(v) => v[row]
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: &'static str,
+    pub data: ColumnData,
+}
+
+fn column(name: &'static str, data: ColumnData) -> Column {
+    Column { name, data }
+}
+
+/// Columns of equal length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    pub fn rows(&self) -> usize {
+        self.columns.first().map(|c| c.data.len()).unwrap_or(0)
+    }
+
+    pub fn column(&self, name: &str) -> Option<&ColumnData> {
+        self.columns
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| &c.data)
+    }
+
+    pub fn write_csv(&self, mut writer: impl Write) -> Result<(), Box<dyn Error>> {
+        let headers: Vec<&str> = self.columns.iter().map(|c| c.name).collect();
+        writeln!(writer, "{}", headers.join(","))?;
+        for row in 0..self.rows() {
+            let fields: Vec<String> = self.columns.iter().map(|c| c.data.csv_field(row)).collect();
+            writeln!(writer, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Any results sheet, see the module docs for the columns.
+pub fn results_table(result: &SessionResult) -> Table {
+    let rows = result.results.rows();
+    let session = result.session().name();
+    let utf8 = |f: fn(&ResultRow) -> Option<String>| ColumnData::Utf8(rows.iter().map(f).collect());
+    Table {
+        columns: vec![
+            column(
+                "session",
+                ColumnData::Utf8(rows.iter().map(|_| Some(session.to_string())).collect()),
+            ),
+            column(
+                "position",
+                ColumnData::UInt32(rows.iter().map(|r| r.position).collect()),
+            ),
+            column("racing_number", utf8(|r| Some(r.racing_number.clone()))),
+            column("driver_tla", utf8(|r| Some(r.driver_tla.clone()))),
+            column("first_name", utf8(|r| Some(r.first_name.clone()))),
+            column("last_name", utf8(|r| Some(r.last_name.clone()))),
+            column("team_name", utf8(|r| Some(r.team_name.clone()))),
+            column("team_colour", utf8(|r| Some(r.team_colour.clone()))),
+            column("time", utf8(|r| r.time.clone())),
+            column("gap_to_leader", utf8(|r| r.gap_to_leader.clone())),
+            column(
+                "laps_behind_leader",
+                ColumnData::UInt32(rows.iter().map(|r| r.laps_behind_leader).collect()),
+            ),
+            column(
+                "finished",
+                ColumnData::Boolean(rows.iter().map(|r| r.finished).collect()),
+            ),
+            column(
+                "points",
+                ColumnData::Int32(rows.iter().map(|r| r.points).collect()),
+            ),
+            column("q1", utf8(|r| r.q1.clone())),
+            column("q2", utf8(|r| r.q2.clone())),
+            column("q3", utf8(|r| r.q3.clone())),
+        ],
+    }
+}
+
+/// Laps of any number of drivers, by racing number.
+pub fn laps_table<'a>(drivers: impl IntoIterator<Item = (&'a str, &'a [LapTrace])>) -> Table {
+    let laps: Vec<(&str, &LapTrace)> = drivers
+        .into_iter()
+        .flat_map(|(number, laps)| laps.iter().map(move |lap| (number, lap)))
+        .collect();
+    Table {
+        columns: vec![
+            column(
+                "racing_number",
+                ColumnData::Utf8(laps.iter().map(|(n, _)| Some(n.to_string())).collect()),
+            ),
+            column(
+                "lap_number",
+                ColumnData::UInt32(laps.iter().map(|(_, l)| Some(l.lap_number)).collect()),
+            ),
+            column(
+                "start",
+                ColumnData::Timestamp(laps.iter().map(|(_, l)| Some(l.start)).collect()),
+            ),
+            column(
+                "end",
+                ColumnData::Timestamp(laps.iter().map(|(_, l)| l.end).collect()),
+            ),
+            column(
+                "lap_time_ms",
+                ColumnData::Int64(
+                    laps.iter()
+                        .map(|(_, l)| l.end.map(|end| (end - l.start).num_milliseconds()))
+                        .collect(),
+                ),
+            ),
+        ],
+    }
+}
+
+/// Car data aligned on the position samples (see [`crate::telemetry::trace::merge`]), lap by
+/// lap for any number of drivers.
+pub fn telemetry_table<'a>(drivers: impl IntoIterator<Item = (&'a str, &'a [LapTrace])>) -> Table {
+    let points: Vec<(&str, u32, &TelemetryPoint)> = drivers
+        .into_iter()
+        .flat_map(|(number, laps)| {
+            laps.iter()
+                .flat_map(move |lap| lap.points.iter().map(move |p| (number, lap.lap_number, p)))
+        })
+        .collect();
+    let float = |f: fn(&TelemetryPoint) -> f64| {
+        ColumnData::Float64(points.iter().map(|(_, _, p)| Some(f(p))).collect())
+    };
+    let boolean = |f: fn(&TelemetryPoint) -> bool| {
+        ColumnData::Boolean(points.iter().map(|(_, _, p)| Some(f(p))).collect())
+    };
+    Table {
+        columns: vec![
+            column(
+                "racing_number",
+                ColumnData::Utf8(points.iter().map(|(n, _, _)| Some(n.to_string())).collect()),
+            ),
+            column(
+                "lap_number",
+                ColumnData::UInt32(points.iter().map(|(_, l, _)| Some(*l)).collect()),
+            ),
+            column(
+                "utc",
+                ColumnData::Timestamp(points.iter().map(|(_, _, p)| Some(p.utc)).collect()),
+            ),
+            column("distance", float(|p| p.distance)),
+            column("x", float(|p| p.x)),
+            column("y", float(|p| p.y)),
+            column("z", float(|p| p.z)),
+            column("speed", float(|p| p.speed)),
+            column("throttle", float(|p| p.throttle)),
+            column("brake", boolean(|p| p.brake)),
+            column(
+                "gear",
+                ColumnData::UInt32(points.iter().map(|(_, _, p)| Some(p.gear as u32)).collect()),
+            ),
+            column("rpm", float(|p| p.rpm)),
+            column("drs", boolean(|p| p.drs)),
+        ],
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow {
+    use std::{error::Error, io::Write, sync::Arc};
+
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray,
+        TimestampMillisecondArray, UInt32Array,
+    };
+    use arrow_ipc::writer::FileWriter;
+    use arrow_schema::{Field, Schema};
+    use parquet::arrow::ArrowWriter;
+
+    use super::{ColumnData, Table};
+
+    impl ColumnData {
+        fn to_array(&self) -> ArrayRef {
+            match self {
+                ColumnData::Utf8(v) => Arc::new(StringArray::from(v.clone())),
+                ColumnData::UInt32(v) => Arc::new(UInt32Array::from(v.clone())),
+                ColumnData::Int32(v) => Arc::new(Int32Array::from(v.clone())),
+                ColumnData::Int64(v) => Arc::new(Int64Array::from(v.clone())),
+                ColumnData::Float64(v) => Arc::new(Float64Array::from(v.clone())),
+                ColumnData::Boolean(v) => Arc::new(BooleanArray::from(v.clone())),
+                ColumnData::Timestamp(v) => Arc::new(
+                    TimestampMillisecondArray::from(
+                        v.iter()
+                            .map(|t| t.map(|t| t.timestamp_millis()))
+                            .collect::<Vec<_>>(),
+                    )
+                    .with_timezone("UTC"),
+                ),
+            }
+        }
+    }
+
+    impl Table {
+        pub fn to_record_batch(&self) -> Result<RecordBatch, Box<dyn Error>> {
+            let arrays: Vec<ArrayRef> = self.columns.iter().map(|c| c.data.to_array()).collect();
+            let fields: Vec<Field> = self
+                .columns
+                .iter()
+                .zip(&arrays)
+                .map(|(c, a)| Field::new(c.name, a.data_type().clone(), true))
+                .collect();
+            Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+        }
+
+        /// Arrow IPC file format (`.arrow`, `.feather`).
+        pub fn write_arrow(&self, writer: impl Write) -> Result<(), Box<dyn Error>> {
+            let batch = self.to_record_batch()?;
+            let mut writer = FileWriter::try_new(writer, &batch.schema())?;
+            writer.write(&batch)?;
+            writer.finish()?;
+            Ok(())
+        }
+
+        pub fn write_parquet(&self, writer: impl Write + Send) -> Result<(), Box<dyn Error>> {
+            let batch = self.to_record_batch()?;
+            let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Typed identifiers, so a season, a round and a meeting key can't be mixed up. [`MeetingKey`]
+//! derefs to `str` and can be passed wherever a `meeting_key: &str` is taken.
+
+use std::{error::Error, fmt, ops::Deref, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lookup::search,
+    results::{get_results, SessionResult},
+    schedule::{get_calendar, get_timetables, Event, Timetable},
+    standings::{get_standings, Standings},
+    weekend_common::Sessions,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Season(pub u32);
+
+/// Number of a round in its season, testing doesn't have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Round(pub u32);
+
+/// Key of a meeting on formula1.com, like `1214` for Silverstone 2023.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MeetingKey(pub String);
+
+/// A session of a meeting, like the race of `1214`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SessionRef {
+    pub meeting: MeetingKey,
+    pub session: Sessions,
+}
+
+impl Season {
+    pub fn calendar(self) -> Result<Vec<Event>, Box<dyn Error>> {
+        get_calendar(&self.to_string())
+    }
+
+    /// The event of a round, e.g. `Season(2023).round(10)` for Silverstone.
+    pub fn round(self, round: impl Into<Round>) -> Result<Event, Box<dyn Error>> {
+        let round = round.into();
+        round
+            .find(&self.calendar()?)
+            .cloned()
+            .ok_or_else(|| format!("{self} has no round {round}.").into())
+    }
+
+    pub fn standings(self) -> Result<Standings, Box<dyn Error>> {
+        get_standings(&self.to_string())
+    }
+}
+
+impl Round {
+    pub fn of(event: &Event) -> Option<Round> {
+        event.round().map(Round)
+    }
+
+    pub fn find(self, events: &[Event]) -> Option<&Event> {
+        events.iter().find(|e| Round::of(e) == Some(self))
+    }
+}
+
+impl MeetingKey {
+    pub fn of(event: &Event) -> MeetingKey {
+        MeetingKey(event.meeting_key.clone())
+    }
+
+    pub fn timetables(&self) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        get_timetables(self)
+    }
+
+    pub fn session(&self, session: Sessions) -> SessionRef {
+        SessionRef {
+            meeting: self.clone(),
+            session,
+        }
+    }
+}
+
+impl SessionRef {
+    pub fn results(&self) -> Result<SessionResult, Box<dyn Error>> {
+        get_results(&self.session, &self.meeting)
+    }
+}
+
+/// The event best matching `name`, like `silverstone`, `British GP` or `GBR`, see
+/// [`crate::lookup::search`].
+pub fn match_meeting<'a>(events: &'a [Event], name: &str) -> Option<&'a Event> {
+    search(events, name).first().map(|c| c.event)
+}
+
+/// The event of a season by name, e.g. `find_meeting("Silverstone", 2023)`.
+pub fn find_meeting(name: &str, season: impl Into<Season>) -> Result<Event, Box<dyn Error>> {
+    let season = season.into();
+    match_meeting(&season.calendar()?, name)
+        .cloned()
+        .ok_or_else(|| format!("No event matching `{name}` in {season}.").into())
+}
+
+impl From<u32> for Season {
+    fn from(year: u32) -> Self {
+        Season(year)
+    }
+}
+
+impl From<u32> for Round {
+    fn from(round: u32) -> Self {
+        Round(round)
+    }
+}
+
+impl From<&str> for MeetingKey {
+    fn from(key: &str) -> Self {
+        MeetingKey(key.to_string())
+    }
+}
+
+impl From<String> for MeetingKey {
+    fn from(key: String) -> Self {
+        MeetingKey(key)
+    }
+}
+
+impl From<&Event> for MeetingKey {
+    fn from(event: &Event) -> Self {
+        MeetingKey::of(event)
+    }
+}
+
+impl Deref for MeetingKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Season {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Season)
+    }
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Round {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for MeetingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for SessionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.meeting, self.session.name())
+    }
+}
@@ -1,9 +1,25 @@
 use std::io::Error;
 
+pub mod analysis;
+pub mod clock;
+#[cfg(feature = "sqlite")]
+pub mod db;
+pub mod ergast;
+pub mod export;
+pub mod ids;
+pub mod lookup;
+pub mod notify;
+pub mod openf1;
 pub mod results;
 pub mod schedule;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod source;
+pub mod standings;
 pub mod telemetry;
+pub mod upcoming;
 pub mod utils;
+pub mod watch;
 pub mod weekend_common;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -15,16 +31,111 @@ pub fn add(left: usize, right: usize) -> usize {
 }
 
 #[cfg(test)]
+// The network tests below keep `pretty` around for the commented out prints.
+#[allow(unused_variables)]
 mod tests {
 
-    use chrono::DateTime;
+    use std::{error::Error, io::Write};
+
+    use base64::{engine::general_purpose, Engine};
+    use chrono::{DateTime, Duration, Utc};
+    use flate2::{write::DeflateEncoder, Compression};
+    use serde_json::json;
 
     use crate::{
-        results::{get_results, DriverQualifyingResult, ResultSheet, SessionResult},
-        schedule::{get_calendar, get_current_weekend, get_timetables},
+        results::{
+            get_results, DriverQualifyingResult, RaceResult, ResultSheet, SessionResult,
+            SprintResult,
+        },
+        schedule::{get_calendar, get_current_weekend, get_timetables, Event, Timetable},
+        standings::compute_standings,
+        telemetry::{
+            car_data::{parse_car_data, CarDataSample},
+            common::StreamMessage,
+            playback::{read_json_stream, Playback},
+            position::{PositionSample, PositionStatus},
+            race_control::{
+                Flag, RaceControlEvent, RaceControlTimeline, SessionStatus, TrackStatus,
+            },
+            relay::Relay,
+            stints::{Compound, StintTracker},
+            team_radio::TeamRadioIndex,
+            timing::{TimeColor, TimingState},
+            trace::{merge, split_laps},
+            track_map::{CornerDirection, TrackMap},
+            weather::WeatherSeries,
+        },
         weekend_common::{Sessions, Status},
     };
 
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    /// Local stand-in for the formula1.com api, see [`serve_routes`].
+    fn formula1(routes: &[(&str, serde_json::Value)]) -> crate::source::Formula1 {
+        crate::source::Formula1::with_base_url(serve_routes(routes))
+    }
+
+    /// Local stand-in for a json api, answers the paths of `routes` (like
+    /// `fom-results/timetables?meeting=1214`) with their json and anything else with a 404.
+    /// Returns the base url.
+    fn serve_routes(routes: &[(&str, serde_json::Value)]) -> String {
+        let routes: std::collections::HashMap<String, String> = routes
+            .iter()
+            .map(|(path, body)| (format!("/{path}"), body.to_string()))
+            .collect();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                std::io::BufRead::read_line(&mut reader, &mut request).unwrap();
+                let mut line = String::new();
+                while std::io::BufRead::read_line(&mut reader, &mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let (status, body) = match routes.get(path) {
+                    Some(body) => ("200 OK", body.as_str()),
+                    None => ("404 Not Found", "{}"),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{addr}/")
+    }
+
+    /// Local stand-in for a http server, answers `requests` requests with `body` and
+    /// returns the base url.
+    fn serve(requests: usize, body: &'static [u8]) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while std::io::BufRead::read_line(&mut reader, &mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+        format!("http://{addr}/")
+    }
+
     #[test]
     fn schedule_year_calendar() {
         // println!("{}", schedule::get_timetables("1214").unwrap())
@@ -84,4 +195,1966 @@ mod tests {
             println!("{}", pretty);
         }
     }
+
+    #[test]
+    fn car_data_compressed_payload() {
+        let payload = json!({"Entries": [{
+            "Utc": "2023-07-09T14:03:00.1234567Z",
+            "Cars": {"44": {"Channels": {"0": 11000, "2": 281, "3": 7, "4": 100, "5": 0, "45": 12}}}
+        }]});
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload.to_string().as_bytes()).unwrap();
+        let message = StreamMessage {
+            topic: "CarData.z".to_string(),
+            timestamp: utc("2023-07-09T14:03:00Z"),
+            data: json!(general_purpose::STANDARD.encode(encoder.finish().unwrap())),
+        };
+        assert_eq!(message.topic_name(), "CarData");
+        let samples = parse_car_data(message.payload().unwrap()).unwrap();
+        let sample = &samples["44"][0];
+        assert_eq!((sample.rpm, sample.speed, sample.gear), (11000, 281, 7));
+        assert_eq!((sample.throttle, sample.brake, sample.drs), (100, 0, 12));
+    }
+
+    #[test]
+    fn car_data_merged_onto_distance() {
+        let start = utc("2023-07-09T14:00:00Z");
+        // 180 km/h == 50 m/s
+        let car_data: Vec<CarDataSample> = (0..=40)
+            .map(|i| CarDataSample {
+                utc: start + Duration::milliseconds(i * 250),
+                rpm: 10000,
+                speed: 180,
+                gear: 6,
+                throttle: 100,
+                brake: 0,
+                drs: 8,
+            })
+            .collect();
+        let positions: Vec<PositionSample> = (0..=35)
+            .map(|i| PositionSample {
+                utc: start + Duration::milliseconds(i * 270),
+                status: PositionStatus::OnTrack,
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            })
+            .collect();
+        let points = merge(&car_data, &positions);
+        assert_eq!(points.len(), positions.len());
+        assert!((points[10].distance - 135.0).abs() < 1e-6);
+
+        let laps = split_laps(&points, &[start, start + Duration::seconds(5)]);
+        assert_eq!(laps.len(), 2);
+        assert!(laps[1].points[0].distance.abs() < 15.0);
+        let resampled = laps[0].resample(50.0);
+        assert_eq!(resampled[1].distance, 50.0);
+        assert_eq!(resampled[1].utc, start + Duration::seconds(1));
+        let delta = laps[1].time_delta(&laps[0], 50.0);
+        assert!(delta.iter().all(|(_, d)| d.abs() < 1e-2));
+        assert!(laps[0].resample(0.0).is_empty());
+        assert!(laps[0].resample(1e-20).is_empty());
+        assert!(laps[0].resample(1e-6).is_empty());
+        assert!(laps[1].time_delta(&laps[0], -50.0).is_empty());
+    }
+
+    #[test]
+    fn track_map_from_stadium_lap() {
+        // Counter-clockwise: 500 m straight, 180 degree left hander (r = 100 m), back again.
+        let mut xy = Vec::new();
+        for i in 0..100 {
+            xy.push((i as f64 * 5.0, 0.0));
+        }
+        for i in 0..=60 {
+            let a = -std::f64::consts::FRAC_PI_2 + std::f64::consts::PI * i as f64 / 60.0;
+            xy.push((500.0 + 100.0 * a.cos(), 100.0 + 100.0 * a.sin()));
+        }
+        for i in 1..100 {
+            xy.push((500.0 - i as f64 * 5.0, 200.0));
+        }
+        for i in 0..=60 {
+            let a = std::f64::consts::FRAC_PI_2 + std::f64::consts::PI * i as f64 / 60.0;
+            xy.push((100.0 * a.cos(), 100.0 + 100.0 * a.sin()));
+        }
+        let start = utc("2023-07-09T14:00:00Z");
+        let lap: Vec<PositionSample> = xy
+            .into_iter()
+            .enumerate()
+            .map(|(i, (x, y))| PositionSample {
+                utc: start + Duration::milliseconds(i as i64 * 100),
+                status: PositionStatus::OnTrack,
+                x: x * 10.0,
+                y: y * 10.0,
+                z: 0.0,
+            })
+            .collect();
+        let map = TrackMap::from_positions("Stadium", &lap).unwrap();
+        let expected = 1000.0 + 2.0 * std::f64::consts::PI * 100.0;
+        assert!((map.length - expected).abs() < 5.0);
+        assert_eq!(map.corners.len(), 2);
+        assert!(map
+            .corners
+            .iter()
+            .all(|c| c.direction == CornerDirection::Left));
+        assert!((map.corners[0].apex.distance - 657.0).abs() < 30.0);
+        assert!((map.corners[0].angle - 180.0).abs() < 15.0);
+        assert_eq!(map.corner_at(100.0).unwrap().number, 1);
+        assert_eq!(map.marshal_sectors.last().unwrap().end_distance, map.length);
+
+        let dir = std::env::temp_dir().join("f1_track_maps");
+        map.save(&dir).unwrap();
+        let loaded = TrackMap::load(&dir, "Stadium").unwrap();
+        assert_eq!(loaded.corners, map.corners);
+        assert_eq!(loaded.outline.len(), map.outline.len());
+    }
+
+    #[test]
+    fn race_control_timeline() {
+        let start = utc("2023-07-09T14:00:00Z");
+        let mut messages = read_json_stream(
+            "SessionStatus",
+            "\u{feff}00:00:00.000{\"Status\":\"Inactive\"}\r\n00:05:00.000{\"Status\":\"Started\"}\r\n",
+            start,
+        )
+        .unwrap();
+        messages.extend(
+            read_json_stream(
+                "TrackStatus",
+                "00:05:00.000{\"Status\":\"1\",\"Message\":\"AllClear\"}\n\
+                 00:20:00.000{\"Status\":\"4\",\"Message\":\"SCDeployed\"}\n",
+                start,
+            )
+            .unwrap(),
+        );
+        let rcm = json!({"Messages": {
+            "1": {"Utc": "2023-07-09T14:12:00", "Lap": 3, "Category": "Drs", "Status": "ENABLED", "Message": "DRS ENABLED"},
+            "0": {"Utc": "2023-07-09T14:05:00", "Lap": 1, "Category": "Flag", "Flag": "GREEN", "Scope": "Track", "Message": "GREEN LIGHT - PIT EXIT OPEN"},
+            "2": {"Utc": "2023-07-09T14:15:00", "Lap": 5, "Category": "Other", "Message": "CAR 44 (HAM) TIME 1:31.234 DELETED - TRACK LIMITS AT TURN 9 LAP 4 15:14:02"},
+            "3": {"Utc": "2023-07-09T14:18:00", "Lap": 7, "Category": "Other", "Message": "FIA STEWARDS: 5 SECOND TIME PENALTY FOR CAR 1 (VER) - CAUSING A COLLISION"}
+        }});
+        messages.push(StreamMessage {
+            topic: "RaceControlMessages".to_string(),
+            timestamp: utc("2023-07-09T14:18:00Z"),
+            data: rcm,
+        });
+        let timeline =
+            RaceControlTimeline::from_stream(&mut Playback::from_messages(messages)).unwrap();
+
+        assert_eq!(
+            timeline.session_status_at(start),
+            Some(SessionStatus::Inactive)
+        );
+        assert_eq!(
+            timeline.session_status_at(utc("2023-07-09T14:06:00Z")),
+            Some(SessionStatus::Started)
+        );
+        assert_eq!(timeline.track_status_at(utc("2023-07-09T14:04:00Z")), None);
+        assert_eq!(
+            timeline.track_status_at(utc("2023-07-09T14:10:00Z")),
+            Some(TrackStatus::AllClear)
+        );
+        assert_eq!(
+            timeline.track_status_at(utc("2023-07-09T14:30:00Z")),
+            Some(TrackStatus::SafetyCarDeployed)
+        );
+        assert!(!timeline.drs_enabled_at(utc("2023-07-09T14:10:00Z")));
+        assert!(timeline.drs_enabled_at(utc("2023-07-09T14:13:00Z")));
+        assert!(matches!(
+            timeline.messages[0].event(),
+            RaceControlEvent::Flag {
+                flag: Flag::Green,
+                ..
+            }
+        ));
+        assert_eq!(
+            timeline.deleted_lap_times()[0].1,
+            RaceControlEvent::LapTimeDeleted {
+                racing_number: Some("44".to_string()),
+                time: Some("1:31.234".to_string()),
+            }
+        );
+        assert_eq!(
+            timeline.penalties()[0].1,
+            RaceControlEvent::Penalty {
+                racing_number: Some("1".to_string()),
+                penalty: "5 SECOND TIME PENALTY".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn team_radio_index_and_download() {
+        let messages = vec![
+            StreamMessage {
+                topic: "DriverList".to_string(),
+                timestamp: utc("2023-07-09T13:00:00Z"),
+                data: json!({"44": {"RacingNumber": "44", "Tla": "HAM", "Line": 1}}),
+            },
+            StreamMessage {
+                topic: "LapCount".to_string(),
+                timestamp: utc("2023-07-09T14:10:00Z"),
+                data: json!({"CurrentLap": 7, "TotalLaps": 52}),
+            },
+            StreamMessage {
+                topic: "TeamRadio".to_string(),
+                timestamp: utc("2023-07-09T14:12:00Z"),
+                data: json!({"Captures": {"0": {
+                    "Utc": "2023-07-09T14:11:58.123Z",
+                    "RacingNumber": "44",
+                    "Path": "TeamRadio/LEWHAM01_44_20230709_151158.mp3"
+                }}}),
+            },
+        ];
+        let index = TeamRadioIndex::from_stream(&mut Playback::from_messages(messages)).unwrap();
+        let clip = index.for_driver("HAM").next().unwrap();
+        assert_eq!(clip.lap, Some(7));
+        assert_eq!(clip.file_name(), "LEWHAM01_44_20230709_151158.mp3");
+
+        let base_url = serve(1, b"ID3 not really audio");
+        let dir = std::env::temp_dir().join("f1_team_radio");
+        let _ = std::fs::remove_dir_all(&dir);
+        let files = index
+            .download(
+                &base_url,
+                "2023/2023-07-09_British_Grand_Prix/2023-07-09_Race/",
+                &dir,
+            )
+            .unwrap();
+        assert_eq!(std::fs::read(&files[0]).unwrap(), b"ID3 not really audio");
+        // Already downloaded, the stand-in only answers once.
+        index.download(&base_url, "", &dir).unwrap();
+    }
+
+    #[test]
+    fn weather_summary_per_session() {
+        let timetables: Vec<Timetable> = serde_json::from_value(json!([
+            {"state": "completed", "session": "p1", "description": "Practice 1",
+             "startTime": "2023-07-07T12:30:00", "endTime": "2023-07-07T13:30:00", "gmtOffset": "+01:00"},
+            {"state": "upcoming", "session": "r", "description": "Race",
+             "startTime": "2023-07-09T15:00:00", "endTime": "2023-07-09T17:00:00", "gmtOffset": "+01:00"}
+        ]))
+        .unwrap();
+        assert_eq!(timetables[0].start_utc(), utc("2023-07-07T11:30:00Z"));
+
+        let stream: String = [
+            ("00:00:00.000", "19.0", "0"),
+            ("00:20:00.000", "21.0", "1"),
+            ("00:40:00.000", "23.0", "0"),
+            ("01:10:00.000", "30.0", "0"),
+        ]
+        .iter()
+        .map(|(offset, air, rain)| {
+            format!(
+                "{offset}{{\"AirTemp\":\"{air}\",\"Humidity\":\"60.0\",\"Pressure\":\"1010.0\",\"Rainfall\":\"{rain}\",\"TrackTemp\":\"35.0\",\"WindDirection\":\"180\",\"WindSpeed\":\"1.5\"}}\n"
+            )
+        })
+        .collect();
+        let messages =
+            read_json_stream("WeatherData", &stream, utc("2023-07-07T11:30:00Z")).unwrap();
+        let series = WeatherSeries::from_stream(&mut Playback::from_messages(messages)).unwrap();
+
+        // Read back, the timestamps survive.
+        let series: WeatherSeries =
+            serde_json::from_str(&serde_json::to_string(&series).unwrap()).unwrap();
+        let summaries = series.session_summaries(&timetables);
+        let practice = summaries[0].1.as_ref().unwrap();
+        assert_eq!(practice.samples, 3);
+        assert_eq!(
+            (
+                practice.air_temp.min,
+                practice.air_temp.max,
+                practice.air_temp.mean
+            ),
+            (19.0, 23.0, 21.0)
+        );
+        assert_eq!(
+            practice.rain_periods,
+            vec![(utc("2023-07-07T11:50:00Z"), utc("2023-07-07T12:10:00Z"))]
+        );
+        assert!(summaries[1].1.is_none());
+    }
+
+    fn event(key: &str, name: &str, location: &str, country: &str, round_text: &str) -> Event {
+        serde_json::from_value(json!({
+            "meetingKey": key, "meetingName": name, "meetingLocation": location,
+            "meetingCountryName": country, "roundText": round_text,
+            "meetingOfficialName": format!("FORMULA 1 {} 2023", name.to_uppercase()),
+            "meetingStartDate": "2023-07-07T00:00:00", "meetingEndDate": "2023-07-09T00:00:00",
+            "gmtOffset": "+01:00", "status": "completed", "circuitMediumImage": "",
+        }))
+        .unwrap()
+    }
+
+    /// Knows round 10 of 2023 under `{prefix}10`, fails results unless `results` is set.
+    struct Fake {
+        prefix: &'static str,
+        results: bool,
+    }
+    impl crate::source::DataSource for Fake {
+        fn name(&self) -> &str {
+            self.prefix
+        }
+        fn calendar(&self, _: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+            Ok(vec![event(
+                &format!("{}10", self.prefix),
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            )])
+        }
+        fn timetables(&self, _: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+            Err("no timetables".into())
+        }
+        fn results(&self, _: &Sessions, key: &str) -> Result<SessionResult, Box<dyn Error>> {
+            if !self.results || key != format!("{}10", self.prefix) {
+                return Err(format!("no results for {key}").into());
+            }
+            Ok(SessionResult {
+                state: Status::Completed,
+                session_info: None,
+                results: ResultSheet::Race(vec![race_result("HAM", "1")]),
+            })
+        }
+        fn standings(&self, _: &str) -> Result<crate::standings::Standings, Box<dyn Error>> {
+            Err("no standings".into())
+        }
+    }
+
+    fn quali_result(tla: &str, team: &str, times: &[&str]) -> DriverQualifyingResult {
+        let segment = |i: usize| {
+            times
+                .get(i)
+                .map(|t| json!({"classifiedTime": t, "completionStatusCode": "OK"}))
+        };
+        serde_json::from_value(json!({
+            "q1": segment(0), "q2": segment(1), "q3": segment(2),
+            "teamColourCode": "", "driverTLA": tla, "teamName": team, "racingNumber": "",
+            "positionNumber": "", "driverFirstName": "", "driverLastName": "",
+            "driverNameFormat": "",
+        }))
+        .unwrap()
+    }
+
+    fn race_result(tla: &str, position: &str) -> RaceResult {
+        serde_json::from_value(json!({
+            "teamColourCode": "6CD3BF", "driverTLA": tla, "teamName": "Mercedes",
+            "racingNumber": "44", "positionNumber": position, "driverFirstName": "Lewis",
+            "driverLastName": "Hamilton", "driverNameFormat": "", "gapToPrevious": null,
+            "gapToLeader": null, "completionStatusCode": "OK", "raceTime": "1:25:16.938",
+            "driverReference": "", "lapsBehindLeader": null, "racePoints": 15, "driverImage": ""
+        }))
+        .unwrap()
+    }
+
+    fn sprint_result(tla: &str, position: &str) -> SprintResult {
+        serde_json::from_value(json!({
+            "teamColourCode": "6CD3BF", "driverTLA": tla, "teamName": "Mercedes",
+            "racingNumber": "44", "positionNumber": position, "driverFirstName": "Lewis",
+            "driverLastName": "Hamilton", "driverNameFormat": "", "gapToPrevious": null,
+            "gapToLeader": null, "completionStatusCode": "OK", "sprintQualifyingTime": "",
+            "driverReference": "", "lapsBehindLeader": null, "sprintQualifyingPoints": 8,
+            "driverImage": ""
+        }))
+        .unwrap()
+    }
+
+    /// A completed results sheet the way formula1.com wraps it.
+    fn sheet(json_key: &str, results: ResultSheet) -> serde_json::Value {
+        let result = SessionResult {
+            state: Status::Completed,
+            session_info: None,
+            results,
+        };
+        json!({ json_key: result })
+    }
+
+    #[test]
+    fn stints_and_pit_stops() {
+        let at = utc("2023-07-09T14:00:00Z");
+        let message = |topic: &str, data| StreamMessage {
+            topic: topic.to_string(),
+            timestamp: at,
+            data,
+        };
+        let messages = vec![
+            message(
+                "DriverList",
+                json!({"44": {"RacingNumber": "44", "Tla": "HAM"}}),
+            ),
+            message(
+                "TimingAppData",
+                json!({"Lines": {"44": {"RacingNumber": "44", "Stints": [
+                    {"Compound": "MEDIUM", "New": "true", "StartLaps": 0, "TotalLaps": 0}
+                ]}}}),
+            ),
+            message(
+                "TimingAppData",
+                json!({"Lines": {"44": {"Stints": {"0": {"TotalLaps": 20}}}}}),
+            ),
+            message(
+                "TimingAppData",
+                json!({"Lines": {"44": {"Stints": {"1": {
+                    "Compound": "HARD", "New": "false", "StartLaps": 3, "TotalLaps": 3
+                }}}}}),
+            ),
+            message(
+                "PitLaneTimeCollection",
+                json!({"PitTimes": {"44": {"RacingNumber": "44", "Duration": "22.4", "Lap": "21"}}}),
+            ),
+            message(
+                "TimingAppData",
+                json!({"Lines": {"44": {"Stints": {"1": {"TotalLaps": 35}}}}}),
+            ),
+        ];
+        let tracker = StintTracker::from_stream(&mut Playback::from_messages(messages)).unwrap();
+        let strategy = tracker.strategy();
+        let ham = strategy.driver("HAM").unwrap();
+        assert_eq!(ham.compounds(), vec![Compound::Medium, Compound::Hard]);
+        assert_eq!((ham.stints[0].start_lap, ham.stints[0].end_lap), (1, 20));
+        assert_eq!((ham.stints[1].start_lap, ham.stints[1].end_lap), (21, 52));
+        assert!(!ham.stints[1].new);
+        assert_eq!(ham.stints[1].tyre_age_at_end(), 35);
+        assert_eq!(ham.pit_stops.len(), 1);
+        assert_eq!(
+            (ham.pit_stops[0].in_lap, ham.pit_stops[0].out_lap),
+            (20, 21)
+        );
+        assert_eq!(ham.pit_stops[0].pit_lane_time, Some(22.4));
+        assert_eq!(ham.pit_stops[0].stationary_time, None);
+
+        let results = vec![race_result("HAM", "3"), race_result("VER", "1")];
+        let joined = strategy.join(&results);
+        assert_eq!(joined[0].1.unwrap().racing_number, "44");
+        assert!(joined[1].1.is_none());
+    }
+
+    #[test]
+    fn timing_state_tower() {
+        let at = utc("2023-07-09T14:00:00Z");
+        let message = |topic: &str, data| StreamMessage {
+            topic: topic.to_string(),
+            timestamp: at,
+            data,
+        };
+        let mut state = TimingState::default();
+        for m in [
+            message(
+                "DriverList",
+                json!({"1": {"RacingNumber": "1", "Tla": "VER", "Line": 1},
+                                         "44": {"RacingNumber": "44", "Tla": "HAM", "Line": 2}}),
+            ),
+            message(
+                "TimingData",
+                json!({"Lines": {
+                    "1": {"Position": "1", "Sectors": [{"Value": ""}, {"Value": ""}, {"Value": ""}]},
+                    "44": {"Position": "2", "GapToLeader": "+0.5", "IntervalToPositionAhead": {"Value": "+0.5"}}
+                }}),
+            ),
+            message(
+                "TimingData",
+                json!({"Lines": {
+                    "1": {"Position": "2", "Sectors": {"1": {"Value": "30.1", "OverallFastest": true}}},
+                    "44": {"Position": "1", "InPit": true}
+                }}),
+            ),
+            message(
+                "TimingAppData",
+                json!({"Lines": {"44": {"Stints": [{"Compound": "HARD", "TotalLaps": 2}]}}}),
+            ),
+            message("LapCount", json!({"CurrentLap": 2, "TotalLaps": 52})),
+            message(
+                "RaceControlMessages",
+                json!({"Messages": {"2": {"Utc": "2023-07-09T14:02:00", "Category": "Other",
+                    "Message": "SECOND"}}}),
+            ),
+            message(
+                "RaceControlMessages",
+                json!({"Messages": {"10": {"Utc": "2023-07-09T14:10:00", "Category": "Other",
+                    "Message": "TENTH"}}}),
+            ),
+            message("CarData.z", json!("not decoded")),
+        ] {
+            state.apply(&m).unwrap();
+        }
+        let tower = state.tower();
+        assert_eq!(tower[0].driver_tla, "HAM");
+        assert!(tower[0].in_pit);
+        assert_eq!(
+            (tower[0].compound, tower[0].tyre_age),
+            (Some(Compound::Hard), Some(2))
+        );
+        assert_eq!(tower[0].interval, "+0.5");
+        assert_eq!(tower[1].sectors[1].color, TimeColor::Purple);
+        assert_eq!(tower[1].sectors[0].color, TimeColor::None);
+        assert_eq!(state.lap_count(), Some((2, 52)));
+        let messages: Vec<String> = state
+            .race_control_messages()
+            .into_iter()
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(messages, ["SECOND", "TENTH"]);
+        assert!(state.topic("CarData").is_none());
+    }
+
+    #[test]
+    fn standings_from_race_and_sprint_results() {
+        let row = |tla: &str, team: &str, position: &str, points: i32, points_key: &str| {
+            json!({
+                "teamColourCode": "", "driverTLA": tla, "teamName": team, "racingNumber": "1",
+                "positionNumber": position, "driverFirstName": "", "driverLastName": tla,
+                "driverNameFormat": "", "gapToPrevious": null, "gapToLeader": null,
+                "completionStatusCode": "OK", "raceTime": "", "sprintQualifyingTime": "",
+                "driverReference": "", "lapsBehindLeader": null, points_key: points,
+                "driverImage": ""
+            })
+        };
+        let race = |rows: Vec<serde_json::Value>| {
+            serde_json::from_value(json!({"state": "completed", "session": "r", "results": rows}))
+                .unwrap()
+        };
+        let results = vec![
+            race(vec![
+                row("VER", "Red Bull Racing", "1", 25, "racePoints"),
+                row("HAM", "Mercedes", "2", 18, "racePoints"),
+                row("PER", "Red Bull Racing", "3", 15, "racePoints"),
+            ]),
+            serde_json::from_value(json!({"state": "completed", "session": "s", "results": [
+                row("HAM", "Mercedes", "1", 8, "sprintQualifyingPoints"),
+                row("VER", "Red Bull Racing", "2", 7, "sprintQualifyingPoints"),
+            ]}))
+            .unwrap(),
+            race(vec![
+                row("HAM", "Mercedes", "1", 25, "racePoints"),
+                row("VER", "Red Bull Racing", "2", 18, "racePoints"),
+                row("PER", "Red Bull Racing", "DQ", 0, "racePoints"),
+            ]),
+        ];
+        let standings = compute_standings(&results);
+
+        let drivers: Vec<(&str, i32, u32)> = standings
+            .drivers
+            .iter()
+            .map(|d| (d.driver_tla.as_str(), d.points, d.wins))
+            .collect();
+        assert_eq!(drivers, [("HAM", 51, 1), ("VER", 50, 1), ("PER", 15, 0)]);
+        assert_eq!(standings.drivers[2].position, 3);
+        assert_eq!(standings.constructors[0].team_name, "Red Bull Racing");
+        assert_eq!(standings.constructors[0].points, 65);
+        assert_eq!(standings.constructors[1].wins, 1);
+    }
+
+    #[test]
+    fn standings_add_sprints_of_sprint_weekends() {
+        let session = |session: &str, start: &str| {
+            json!({"state": "completed", "session": session, "description": "",
+                "startTime": start, "endTime": start, "gmtOffset": "+01:00"})
+        };
+        let testing = event("1200", "Pre-Season Testing", "Sakhir", "Bahrain", "");
+        let f1 = formula1(&[
+            (
+                "editorial-eventlisting/events?season=2023",
+                json!({"events": [testing, event("1214", "British Grand Prix", "Silverstone",
+                    "Great Britain", "ROUND 10")]}),
+            ),
+            (
+                "fom-results/timetables?meeting=1214",
+                json!({"timetables": [session("s", "2023-07-08T16:30:00"),
+                    session("r", "2023-07-09T15:00:00")]}),
+            ),
+            (
+                "fom-results/race?meeting=1214",
+                sheet(
+                    "raceResultsRace",
+                    ResultSheet::Race(vec![race_result("HAM", "1")]),
+                ),
+            ),
+            (
+                "fom-results/sprint?meeting=1214",
+                sheet(
+                    "raceResultsSprint",
+                    ResultSheet::Sprint(vec![sprint_result("HAM", "1")]),
+                ),
+            ),
+        ]);
+        let standings = crate::source::DataSource::standings(&f1, "2023").unwrap();
+        assert_eq!(standings.drivers[0].driver_tla, "HAM");
+        assert_eq!(standings.drivers[0].points, 23);
+    }
+
+    #[test]
+    fn relay_sends_snapshot_to_late_joiners() {
+        let relay = Relay::bind("127.0.0.1:0").unwrap();
+        let lap = |lap: u32| StreamMessage {
+            topic: "LapCount".to_string(),
+            timestamp: utc("2023-07-09T14:30:00Z"),
+            data: json!({"CurrentLap": lap, "TotalLaps": 52}),
+        };
+        relay.push(&lap(3)).unwrap();
+
+        let mut sse = std::net::TcpStream::connect(relay.local_addr()).unwrap();
+        sse.write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let (mut ws, _) = tungstenite::connect(format!("ws://{}/", relay.local_addr())).unwrap();
+        let mut ws_event = || -> serde_json::Value {
+            serde_json::from_str(&ws.read().unwrap().into_text().unwrap()).unwrap()
+        };
+        let snapshot = ws_event();
+        assert_eq!(snapshot["type"], "snapshot");
+        assert_eq!(snapshot["state"]["topics"]["LapCount"]["CurrentLap"], 3);
+
+        while relay.clients() < 2 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        relay.push(&lap(4)).unwrap();
+        let message = ws_event();
+        assert_eq!(message["type"], "message");
+        assert_eq!(message["topic"], "LapCount");
+        assert_eq!(message["data"]["CurrentLap"], 4);
+
+        let sse = std::io::BufReader::new(sse);
+        let events: Vec<String> = std::io::BufRead::lines(sse)
+            .map(Result::unwrap)
+            .filter(|l| l.starts_with("event: "))
+            .take(2)
+            .collect();
+        assert_eq!(events, ["event: snapshot", "event: message"]);
+    }
+
+    #[test]
+    fn relay_is_not_held_up_by_a_stalled_client() {
+        let relay = Relay::bind("127.0.0.1:0").unwrap();
+        // Never reads, its socket buffers fill up after a few messages.
+        let mut stalled = std::net::TcpStream::connect(relay.local_addr()).unwrap();
+        stalled
+            .write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let (mut ws, _) = tungstenite::connect(format!("ws://{}/", relay.local_addr())).unwrap();
+        while relay.clients() < 2 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let padding = "x".repeat(256 * 1024);
+        let started = std::time::Instant::now();
+        for lap in 1..=100 {
+            relay
+                .push(&StreamMessage {
+                    topic: "LapCount".to_string(),
+                    timestamp: utc("2023-07-09T14:30:00Z"),
+                    data: json!({"CurrentLap": lap, "TotalLaps": 52, "Padding": padding}),
+                })
+                .unwrap();
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        let last = std::iter::repeat_with(|| ws.read().unwrap())
+            .map(|m| serde_json::from_str::<serde_json::Value>(&m.into_text().unwrap()).unwrap())
+            .find(|event| event["data"]["CurrentLap"] == 100);
+        assert!(last.is_some());
+    }
+
+    #[test]
+    fn relay_skips_unreadable_messages_and_oversized_requests() {
+        struct Frames(std::collections::VecDeque<StreamMessage>);
+        impl crate::telemetry::common::DataStream for Frames {
+            fn next_message(&mut self) -> Option<Result<StreamMessage, Box<dyn Error>>> {
+                self.0.pop_front().map(Ok)
+            }
+        }
+
+        let relay = Relay::bind("127.0.0.1:0").unwrap();
+        let message = |topic: &str, data| StreamMessage {
+            topic: topic.to_string(),
+            timestamp: utc("2023-07-09T14:30:00Z"),
+            data,
+        };
+        let mut frames = Frames(
+            [
+                message("LapCount", json!({"CurrentLap": 3, "TotalLaps": 52})),
+                message("CarData.z", json!("not deflate")),
+                message("LapCount", json!({"CurrentLap": 4})),
+            ]
+            .into(),
+        );
+        let skipped = relay
+            .run(&mut frames, None, std::time::Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(skipped, 1);
+
+        let mut oversized = std::net::TcpStream::connect(relay.local_addr()).unwrap();
+        let padding = format!("X-Padding: {}\r\n", "x".repeat(64));
+        oversized
+            .write_all(format!("GET /events HTTP/1.1\r\n{}", padding.repeat(200)).as_bytes())
+            .unwrap();
+        let started = std::time::Instant::now();
+        let mut buf = [0; 64];
+        let read = std::io::Read::read(&mut oversized, &mut buf);
+        assert!(matches!(read, Ok(0) | Err(_)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        let (mut ws, _) = tungstenite::connect(format!("ws://{}/", relay.local_addr())).unwrap();
+        let snapshot: serde_json::Value =
+            serde_json::from_str(&ws.read().unwrap().into_text().unwrap()).unwrap();
+        assert_eq!(snapshot["state"]["topics"]["LapCount"]["CurrentLap"], 4);
+        assert_eq!(relay.clients(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_results_are_upserted() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::create_schema(&conn).unwrap();
+        let mut result: crate::results::SessionResult = serde_json::from_value(json!({
+            "state": "completed", "session": "r",
+            "results": [serde_json::to_value(race_result("HAM", "3")).unwrap()]
+        }))
+        .unwrap();
+        crate::db::save_result(&conn, 2023, "1214", &result).unwrap();
+        if let crate::results::ResultSheet::Race(results) = &mut result.results {
+            results[0].position_number = "2".to_string();
+        }
+        crate::db::save_result(&conn, 2023, "1214", &result).unwrap();
+
+        let rows: Vec<(String, u32, i32)> = conn
+            .prepare("SELECT session, position, points FROM results WHERE driver_tla = 'HAM'")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows, [("race".to_string(), 2, 15)]);
+
+        // Same driver, another season and team.
+        if let crate::results::ResultSheet::Race(results) = &mut result.results {
+            results[0].team_name = "Ferrari".to_string();
+        }
+        crate::db::save_result(&conn, 2025, "1254", &result).unwrap();
+        let teams: Vec<(u32, String)> = conn
+            .prepare("SELECT season, team_name FROM drivers WHERE tla = 'HAM' ORDER BY season")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            teams,
+            [
+                (2023, "Mercedes".to_string()),
+                (2025, "Ferrari".to_string())
+            ]
+        );
+        let seasons: u32 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT season) FROM teams WHERE name IN ('Mercedes', 'Ferrari')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(seasons, 2);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_sync_keeps_going_past_unreadable_sessions() {
+        let session = |session: &str, start: &str| {
+            json!({"state": "completed", "session": session, "description": "",
+                "startTime": start, "endTime": start, "gmtOffset": "+02:00"})
+        };
+        let results = |session: &str| format!("fom-results/{session}?meeting=1216");
+        let f1 = formula1(&[
+            (
+                "editorial-eventlisting/events?season=2023",
+                json!({"events": [event("1200", "Pre-Season Testing", "Sakhir", "Bahrain", ""),
+                    event("1216", "Austrian Grand Prix", "Spielberg", "Austria", "ROUND 10")]}),
+            ),
+            (
+                "fom-results/timetables?meeting=1216",
+                json!({"timetables": [session("q", "2023-06-30T17:00:00"),
+                    session("ss", "2023-07-01T12:00:00"), session("s", "2023-07-01T16:30:00"),
+                    session("r", "2023-07-02T15:00:00")]}),
+            ),
+            // The qualifying sheet is broken, the ones after it still get saved.
+            (&results("qualifying"), json!({})),
+            (
+                &results("sprint"),
+                sheet(
+                    "raceResultsSprint",
+                    ResultSheet::Sprint(vec![sprint_result("HAM", "2")]),
+                ),
+            ),
+            (
+                &results("race"),
+                sheet(
+                    "raceResultsRace",
+                    ResultSheet::Race(vec![race_result("HAM", "1")]),
+                ),
+            ),
+        ]);
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::create_schema(&conn).unwrap();
+        let skipped = crate::db::sync_season_from(&mut conn, &f1, "2023").unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(
+            (skipped[0].meeting_key.as_str(), skipped[0].session),
+            ("1216", Sessions::Qualifying)
+        );
+        assert_eq!(
+            skipped[0].to_string(),
+            "1216 qualifying: Key `raceResultsQualifying` not in received json."
+        );
+
+        let count = |sql: &str| -> u32 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM events"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM sessions"), 4);
+        assert_eq!(count("SELECT COUNT(*) FROM results"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM drivers WHERE season = 2023"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM teams WHERE season = 2023"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM laps"), 0);
+    }
+
+    #[test]
+    fn results_export_csv() {
+        let mut dnf = serde_json::to_value(race_result("VER", "NC")).unwrap();
+        dnf["completionStatusCode"] = json!("DNF");
+        dnf["teamName"] = json!("Red Bull Racing, Honda");
+        let mut winner = serde_json::to_value(race_result("HAM", "1")).unwrap();
+        winner["driverLastName"] = json!("Hamilton\r");
+        let result: crate::results::SessionResult = serde_json::from_value(json!({
+            "state": "completed", "session": "r",
+            "results": [winner, dnf]
+        }))
+        .unwrap();
+        let table = crate::export::results_table(&result);
+        assert_eq!(table.rows(), 2);
+
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "session,position,racing_number,driver_tla,first_name,last_name,team_name,\
+             team_colour,time,gap_to_leader,laps_behind_leader,finished,points,q1,q2,q3"
+        );
+        assert_eq!(
+            lines[2],
+            "race,,44,VER,Lewis,Hamilton,\"Red Bull Racing, Honda\",6CD3BF,1:25:16.938,,,\
+             false,15,,,"
+        );
+        assert!(lines[1].starts_with("race,1,44,HAM,Lewis,\"Hamilton\r\",Mercedes,"));
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn laps_export_parquet() {
+        let start = utc("2023-07-09T14:03:00Z");
+        let laps = vec![
+            crate::telemetry::trace::LapTrace {
+                lap_number: 1,
+                start,
+                end: Some(start + Duration::milliseconds(91_250)),
+                points: Vec::new(),
+            },
+            crate::telemetry::trace::LapTrace {
+                lap_number: 2,
+                start: start + Duration::milliseconds(91_250),
+                end: None,
+                points: Vec::new(),
+            },
+        ];
+        let table = crate::export::laps_table([("44", laps.as_slice())]);
+        let path = std::env::temp_dir().join(format!("f1_laps_{}.parquet", std::process::id()));
+        table
+            .write_parquet(std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            std::fs::File::open(&path).unwrap(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0], table.to_record_batch().unwrap());
+    }
+
+    #[test]
+    fn ergast_results_mapped_onto_model() {
+        let body = br#"{"MRData": {"total": "2", "RaceTable": {"Races": [{
+            "season": "2021", "round": "12", "raceName": "Belgian Grand Prix",
+            "Circuit": {"circuitId": "spa", "circuitName": "Spa",
+                "Location": {"locality": "Spa", "country": "Belgium"}},
+            "date": "2021-08-29", "time": "13:00:00Z",
+            "Results": [
+                {"number": "33", "position": "1", "positionText": "1", "points": "12.5",
+                 "Driver": {"driverId": "max_verstappen", "code": "VER",
+                    "givenName": "Max", "familyName": "Verstappen"},
+                 "Constructor": {"constructorId": "red_bull", "name": "Red Bull"},
+                 "status": "Finished", "Time": {"millis": "207071", "time": "3:27.071"}},
+                {"number": "5", "position": "20", "positionText": "R", "points": "0",
+                 "Driver": {"driverId": "vettel", "givenName": "Sebastian",
+                    "familyName": "Vettel"},
+                 "Constructor": {"constructorId": "aston_martin", "name": "Aston Martin"},
+                 "status": "Accident"}
+            ]}]}}}"#;
+        let ergast = crate::ergast::Ergast::with_base_url(serve(1, body));
+        let result =
+            crate::source::DataSource::results(&ergast, &Sessions::Race, "2021-12").unwrap();
+        let rows = result.results.rows();
+        assert_eq!(result.session(), Sessions::Race);
+        assert_eq!(rows[0].driver_tla, "VER");
+        assert_eq!(rows[0].points, Some(13));
+        assert_eq!(rows[0].time.as_deref(), Some("3:27.071"));
+        assert_eq!(rows[1].driver_tla, "VET");
+        assert_eq!(rows[1].position, None);
+        assert_eq!(rows[1].finished, Some(false));
+        assert!(crate::source::DataSource::results(&ergast, &Sessions::Race, "1214").is_err());
+    }
+
+    #[test]
+    fn ergast_standings_over_several_pages() {
+        let driver = |position: &str, code: &str, points: &str| {
+            json!({"position": position, "points": points, "wins": "0",
+                "Driver": {"driverId": code, "code": code, "givenName": "", "familyName": code},
+                "Constructors": [{"constructorId": "ferrari", "name": "Ferrari"}]})
+        };
+        let standings = |total: &str, key: &str, entries: serde_json::Value| {
+            json!({"MRData": {"total": total,
+                "StandingsTable": {"StandingsLists": [{key: entries}]}}})
+        };
+        let ergast = crate::ergast::Ergast::with_base_url(serve_routes(&[
+            (
+                "1952/driverStandings.json?limit=100&offset=0",
+                standings("101", "DriverStandings", json!([driver("1", "ASC", "36")])),
+            ),
+            (
+                "1952/driverStandings.json?limit=100&offset=100",
+                standings(
+                    "101",
+                    "DriverStandings",
+                    json!([driver("101", "RUT", "0.5")]),
+                ),
+            ),
+            (
+                "1952/constructorStandings.json?limit=100&offset=0",
+                standings("0", "ConstructorStandings", json!([])),
+            ),
+        ]));
+        let standings = crate::source::DataSource::standings(&ergast, "1952").unwrap();
+        let drivers: Vec<(u32, &str, i32)> = standings
+            .drivers
+            .iter()
+            .map(|d| (d.position, d.driver_tla.as_str(), d.points))
+            .collect();
+        assert_eq!(drivers, [(1, "ASC", 36), (101, "RUT", 1)]);
+    }
+
+    #[test]
+    fn ergast_sessions_completed_by_now() {
+        let race: crate::ergast::Race = serde_json::from_value(json!({
+            "season": "2023", "round": "10", "raceName": "British Grand Prix",
+            "Circuit": {"circuitId": "silverstone", "circuitName": "Silverstone Circuit",
+                "Location": {"locality": "Silverstone", "country": "UK"}},
+            "date": "2023-07-09", "time": "14:00:00Z",
+            "Qualifying": {"date": "2023-07-08", "time": "15:00:00Z"}
+        }))
+        .unwrap();
+        let states: Vec<Status> = race
+            .timetables(utc("2023-07-08T20:00:00Z"))
+            .unwrap()
+            .iter()
+            .map(|t| t.state)
+            .collect();
+        assert_eq!(states, [Status::Completed, Status::Upcoming]);
+        let event = race.to_event(utc("2023-07-09T16:00:00Z")).unwrap();
+        assert_eq!(event.status, Status::Completed);
+
+        let body = br#"{"MRData": {"total": "1", "RaceTable": {"Races": [{
+            "season": "2021", "round": "12", "raceName": "Belgian Grand Prix",
+            "Circuit": {"circuitId": "spa", "circuitName": "Spa",
+                "Location": {"locality": "Spa", "country": "Belgium"}},
+            "date": "2021-08-29", "time": "13:00:00Z"}]}}}"#;
+        let ergast = crate::ergast::Ergast::with_base_url(serve(1, body))
+            .clock(crate::clock::ManualClock::new(utc("2021-08-29T12:00:00Z")));
+        let race = crate::source::DataSource::timetables(&ergast, "2021-12").unwrap();
+        assert_eq!(race[0].state, Status::Upcoming);
+    }
+
+    #[test]
+    fn openf1_telemetry_mapped_onto_model() {
+        let body = br#"[{"date": "2023-07-09T14:03:12.211000+00:00", "session_key": 9165,
+            "meeting_key": 1213, "driver_number": 44, "rpm": 11141, "speed": 291,
+            "n_gear": 8, "throttle": 99, "brake": 0, "drs": 12}]"#;
+        let openf1 = crate::openf1::OpenF1::with_base_url(serve(1, body));
+        let samples = openf1.car_data(9165, 44).unwrap();
+        assert_eq!(samples[0].utc, utc("2023-07-09T14:03:12.211Z"));
+        assert_eq!(
+            (samples[0].speed, samples[0].gear, samples[0].drs),
+            (291, 8, 12)
+        );
+
+        let body = br#"[{"date": "2023-07-09T14:40:01+00:00", "session_key": 9165,
+            "meeting_key": 1213, "driver_number": null, "lap_number": 3,
+            "category": "SafetyCar", "flag": null, "scope": null, "sector": null,
+            "message": "VIRTUAL SAFETY CAR DEPLOYED"}]"#;
+        let openf1 = crate::openf1::OpenF1::with_base_url(serve(1, body));
+        let messages = openf1.race_control(9165).unwrap();
+        assert_eq!(messages[0].lap, Some(3));
+        assert_eq!(
+            messages[0].event(),
+            RaceControlEvent::VirtualSafetyCarDeployed
+        );
+    }
+
+    #[test]
+    fn openf1_sessions_mapped_onto_model() {
+        let body = br#"[{"session_key": 9140, "meeting_key": 1210, "session_name": "Race",
+            "session_type": "Race", "date_start": "2023-06-18T18:00:00+00:00",
+            "date_end": "2023-06-18T20:00:00+00:00", "gmt_offset": "-04:00:00", "year": 2023}]"#;
+        let openf1 = crate::openf1::OpenF1::with_base_url(serve(1, body));
+        let timetables = crate::source::DataSource::timetables(&openf1, "1210").unwrap();
+        assert_eq!(timetables[0].gmt_offset, "-04:00");
+        assert_eq!(timetables[0].start_utc(), utc("2023-06-18T18:00:00Z"));
+
+        let session: crate::openf1::Session =
+            serde_json::from_slice(&body[1..body.len() - 1]).unwrap();
+        // Serves the results and the drivers, each ignores the other's fields.
+        let body = br#"[{"driver_number": 1, "position": 1, "points": 12.5, "duration": 4500.5,
+            "gap_to_leader": 0, "name_acronym": "VER", "team_name": "Red Bull Racing"}]"#;
+        let openf1 = crate::openf1::OpenF1::with_base_url(serve(2, body));
+        let rows = openf1.session_result(&session).unwrap().results.rows();
+        assert_eq!(rows[0].driver_tla, "VER");
+        assert_eq!(rows[0].points, Some(13));
+    }
+
+    #[test]
+    fn composite_falls_back_with_translated_keys() {
+        use crate::source::{Composite, DataSource};
+        use std::{cell::Cell, rc::Rc};
+
+        struct Counted(Fake, Rc<Cell<usize>>);
+        impl DataSource for Counted {
+            fn name(&self) -> &str {
+                self.0.name()
+            }
+            fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+                self.1.set(self.1.get() + 1);
+                self.0.calendar(year)
+            }
+            fn timetables(&self, key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+                self.0.timetables(key)
+            }
+            fn results(
+                &self,
+                session: &Sessions,
+                key: &str,
+            ) -> Result<SessionResult, Box<dyn Error>> {
+                self.0.results(session, key)
+            }
+            fn standings(&self, year: &str) -> Result<crate::standings::Standings, Box<dyn Error>> {
+                self.0.standings(year)
+            }
+        }
+
+        let calendars = Rc::new(Cell::new(0));
+        let composite = Composite::new(vec![
+            Box::new(Counted(
+                Fake {
+                    prefix: "f1-",
+                    results: false,
+                },
+                calendars.clone(),
+            )),
+            Box::new(Counted(
+                Fake {
+                    prefix: "ergast-",
+                    results: true,
+                },
+                calendars.clone(),
+            )),
+        ]);
+        let calendar = composite.calendar("2023").unwrap();
+        assert_eq!(calendar[0].meeting_key, "f1-10");
+        for _ in 0..3 {
+            let result = composite.results(&Sessions::Race, "f1-10").unwrap();
+            assert_eq!(result.results.rows()[0].driver_tla, "HAM");
+        }
+        // The composite's own calendar and the one translating for the second provider.
+        assert_eq!(calendars.get(), 2);
+        let error = composite.standings("2023").unwrap_err().to_string();
+        assert_eq!(error, "f1-: no standings; ergast-: no standings");
+        assert!(composite.car_data(&Sessions::Race, "f1-10", "HAM").is_err());
+    }
+
+    #[test]
+    fn meetings_found_by_round_and_name() {
+        use crate::ids::{match_meeting, MeetingKey, Round};
+
+        let calendar = vec![
+            event("1141", "Pre-Season Testing", "Sakhir", "Bahrain", "TESTING"),
+            event(
+                "1213",
+                "Austrian Grand Prix",
+                "Spielberg",
+                "Austria",
+                "ROUND 9",
+            ),
+            event(
+                "1214",
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            ),
+        ];
+        let silverstone = Round(10).find(&calendar).unwrap();
+        assert_eq!(MeetingKey::of(silverstone), MeetingKey::from("1214"));
+        assert_eq!(Round::of(&calendar[0]), None);
+        let key = MeetingKey::of(match_meeting(&calendar, "silverstone").unwrap());
+        assert_eq!(key.session(Sessions::Race).to_string(), "1214/race");
+        assert_eq!(&*key, "1214");
+        assert_eq!(
+            match_meeting(&calendar, "British GP").unwrap().meeting_key,
+            "1214"
+        );
+        assert!(match_meeting(&calendar, "Monaco").is_none());
+    }
+
+    #[test]
+    fn events_looked_up_fuzzily() {
+        use crate::lookup::search;
+
+        let calendar = vec![
+            event(
+                "1213",
+                "Austrian Grand Prix",
+                "Spielberg",
+                "Austria",
+                "ROUND 9",
+            ),
+            event(
+                "1214",
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            ),
+            event(
+                "1215",
+                "Hungarian Grand Prix",
+                "Budapest",
+                "Hungary",
+                "ROUND 11",
+            ),
+            event(
+                "1223",
+                "São Paulo Grand Prix",
+                "São Paulo",
+                "Brazil",
+                "ROUND 20",
+            ),
+        ];
+        let best = |query: &str| {
+            search(&calendar, query)
+                .first()
+                .map(|c| c.event.meeting_key.clone())
+        };
+        for query in [
+            "silverstone",
+            "British GP",
+            "GBR",
+            "Great Britain",
+            "silverstne",
+        ] {
+            assert_eq!(best(query).as_deref(), Some("1214"), "{query}");
+        }
+        assert_eq!(best("interlagos").as_deref(), Some("1223"));
+        assert_eq!(best("sao paulo").as_deref(), Some("1223"));
+        assert_eq!(best("Monaco"), None);
+        let ranked = search(&calendar, "Grand Prix");
+        assert_eq!(ranked.len(), 4);
+        assert!(search(&calendar, "Austria")[0].score > 0.9);
+    }
+
+    #[test]
+    fn upcoming_sessions_skip_unreadable_events_and_seasons() {
+        use crate::upcoming::{next_session_from, previous_session_from, weekend_at_from};
+
+        let dated = |key: &str, name: &str, round: &str, start: &str, end: &str| {
+            let mut event = serde_json::to_value(event(key, name, "", "", round)).unwrap();
+            event["meetingStartDate"] = json!(start);
+            event["meetingEndDate"] = json!(end);
+            event
+        };
+        let race = |start: &str| {
+            json!({"timetables": [{"state": "upcoming", "session": "r", "description": "Race",
+                "startTime": start, "endTime": start, "gmtOffset": "+00:00"}]})
+        };
+        // The British Grand Prix lost its timetable, 2024 isn't published.
+        let f1 = formula1(&[
+            (
+                "editorial-eventlisting/events?season=2023",
+                json!({"events": [
+                    dated("1213", "Austrian Grand Prix", "ROUND 9",
+                        "2023-06-30T00:00:00", "2023-07-02T00:00:00"),
+                    dated("1214", "British Grand Prix", "ROUND 10",
+                        "2023-07-07T00:00:00", "2023-07-09T00:00:00"),
+                    dated("1215", "Hungarian Grand Prix", "ROUND 11",
+                        "2023-07-21T00:00:00", "2023-07-23T00:00:00"),
+                ]}),
+            ),
+            (
+                "fom-results/timetables?meeting=1213",
+                race("2023-07-02T13:00:00"),
+            ),
+            (
+                "fom-results/timetables?meeting=1215",
+                race("2023-07-23T13:00:00"),
+            ),
+        ]);
+
+        let now = utc("2023-07-08T12:00:00Z");
+        let next = next_session_from(&f1, now).unwrap().unwrap();
+        assert_eq!(next.event.meeting_key, "1215");
+        let previous = previous_session_from(&f1, now).unwrap().unwrap();
+        assert_eq!(previous.event.meeting_key, "1213");
+        let weekend = weekend_at_from(&f1, now).unwrap().unwrap();
+        assert_eq!(weekend.current_or_next_meeting_key, "1215");
+
+        let after_the_season = utc("2023-12-01T12:00:00Z");
+        assert!(next_session_from(&f1, after_the_season).unwrap().is_none());
+        assert!(weekend_at_from(&f1, after_the_season).unwrap().is_none());
+        assert!(next_session_from(&f1, utc("2025-01-01T00:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn sprint_weekend_phases() {
+        use crate::upcoming::{countdown_to, is_testing, phase_in, WeekendPhase};
+
+        // Local times at Spa 2023, two hours ahead of UTC.
+        let session = |session, start: &str, minutes| Timetable {
+            state: Status::Upcoming,
+            session,
+            description: String::new(),
+            start_date: utc(start),
+            end_date: utc(start) + Duration::minutes(minutes),
+            gmt_offset: "+02:00".to_string(),
+        };
+        let weekend = crate::schedule::CurrentWeekend {
+            season_year: "2023".to_string(),
+            current_or_next_meeting_key: "1216".to_string(),
+            timetables: vec![
+                session(Sessions::Race, "2023-07-30T15:00:00Z", 120),
+                session(Sessions::FreePractice1, "2023-07-28T13:30:00Z", 60),
+                session(Sessions::Qualifying, "2023-07-28T17:00:00Z", 60),
+                session(Sessions::SprintShootout, "2023-07-29T12:00:00Z", 45),
+                session(Sessions::Sprint, "2023-07-29T17:05:00Z", 30),
+            ],
+        };
+        let at = |t: &str| utc(t);
+        assert_eq!(
+            weekend.phase(at("2023-07-28T10:00:00Z")),
+            WeekendPhase::PreWeekend
+        );
+        assert_eq!(
+            weekend.phase(at("2023-07-28T12:00:00Z")),
+            WeekendPhase::Practice
+        );
+        assert_eq!(
+            weekend.phase(at("2023-07-28T18:00:00Z")),
+            WeekendPhase::Qualifying
+        );
+        assert_eq!(
+            weekend.phase(at("2023-07-29T10:30:00Z")),
+            WeekendPhase::Sprint
+        );
+        assert_eq!(
+            weekend.phase(at("2023-07-30T14:00:00Z")),
+            WeekendPhase::Race
+        );
+        assert_eq!(
+            weekend.phase(at("2023-07-30T16:00:00Z")),
+            WeekendPhase::PostWeekend
+        );
+
+        let now = at("2023-07-29T10:15:00Z");
+        let live = weekend.live_session(now).unwrap();
+        assert_eq!(live.session, Sessions::SprintShootout);
+        assert_eq!(weekend.next_session(now).unwrap().session, Sessions::Sprint);
+        assert_eq!(
+            weekend.previous_session(now).unwrap().session,
+            Sessions::Qualifying
+        );
+        assert_eq!(countdown_to(live, now), Duration::minutes(-15));
+
+        let testing = vec![
+            session(Sessions::FreePractice1, "2023-02-23T12:00:00Z", 540),
+            session(Sessions::FreePractice2, "2023-02-24T12:00:00Z", 540),
+        ];
+        assert!(is_testing(&testing) && !is_testing(&weekend.timetables));
+        assert_eq!(
+            phase_in(&testing, at("2023-02-24T08:00:00Z")),
+            WeekendPhase::Testing
+        );
+    }
+
+    #[test]
+    fn weekend_formats_from_timetables() {
+        use crate::weekend_common::WeekendFormat;
+
+        let timetables = |sessions: &[(&str, &str)]| -> Vec<Timetable> {
+            let sessions: Vec<_> = sessions
+                .iter()
+                .map(|(code, start)| {
+                    json!({"state": "completed", "session": code, "description": "",
+                        "startTime": start, "endTime": start, "gmtOffset": "+01:00"})
+                })
+                .collect();
+            serde_json::from_value(json!(sessions)).unwrap()
+        };
+        let imola_2022 = timetables(&[
+            ("p1", "2022-04-22T13:30:00"),
+            ("q", "2022-04-22T17:00:00"),
+            ("p2", "2022-04-23T12:30:00"),
+            ("s", "2022-04-23T16:30:00"),
+            ("r", "2022-04-24T15:00:00"),
+        ]);
+        let spa_2023 = timetables(&[
+            ("p1", "2023-07-28T13:30:00"),
+            ("q", "2023-07-28T17:00:00"),
+            ("ss", "2023-07-29T12:00:00"),
+            ("s", "2023-07-29T17:05:00"),
+            ("r", "2023-07-30T15:00:00"),
+        ]);
+        let miami_2024 = timetables(&[
+            ("p1", "2024-05-03T12:30:00"),
+            ("sq", "2024-05-03T16:30:00"),
+            ("s", "2024-05-04T12:00:00"),
+            ("q", "2024-05-04T16:00:00"),
+            ("r", "2024-05-05T16:00:00"),
+        ]);
+        let hungary_2023 = timetables(&[
+            ("p1", "2023-07-21T13:30:00"),
+            ("p2", "2023-07-21T17:00:00"),
+            ("p3", "2023-07-22T12:30:00"),
+            ("q", "2023-07-22T16:00:00"),
+            ("r", "2023-07-23T15:00:00"),
+        ]);
+        let format = |t: &[Timetable]| WeekendFormat::detect(t).unwrap();
+        assert_eq!(format(&imola_2022), WeekendFormat::Sprint2021);
+        assert_eq!(format(&spa_2023), WeekendFormat::Sprint2023);
+        assert_eq!(format(&miami_2024), WeekendFormat::Sprint2024);
+        assert_eq!(format(&hungary_2023), WeekendFormat::Conventional);
+        assert_eq!(format(&hungary_2023[..2]), WeekendFormat::Testing);
+        assert_eq!(WeekendFormat::detect(&[]), None);
+        assert!(format(&miami_2024).sessions().iter().eq(miami_2024
+            .iter()
+            .map(|t| &t.session)
+            .filter(|s| **s != Sessions::SprintShootout)));
+        assert_eq!(serde_json::to_value(&spa_2023[2]).unwrap()["session"], "ss");
+    }
+
+    #[test]
+    fn schedule_changes_reach_callbacks() {
+        use crate::watch::{ScheduleChange, ScheduleSnapshot, ScheduleWatcher};
+        use std::{cell::RefCell, rc::Rc};
+
+        let race = |start: &str, state: Status| Timetable {
+            state,
+            session: Sessions::Race,
+            description: "Race".to_string(),
+            start_date: utc(start),
+            end_date: utc(start) + Duration::hours(2),
+            gmt_offset: "+02:00".to_string(),
+        };
+        let snapshot = |events: Vec<Event>, race_start: &str| ScheduleSnapshot {
+            timetables: events
+                .iter()
+                .map(|e| {
+                    (
+                        e.meeting_key.clone(),
+                        vec![race(race_start, Status::Upcoming)],
+                    )
+                })
+                .collect(),
+            events,
+        };
+        let imola = event(
+            "1209",
+            "Emilia Romagna Grand Prix",
+            "Imola",
+            "Italy",
+            "ROUND 6",
+        );
+        let monaco = event("1210", "Monaco Grand Prix", "Monaco", "Monaco", "ROUND 7");
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut watcher = ScheduleWatcher::new("2023");
+        let log = seen.clone();
+        watcher.on_change(move |change| log.borrow_mut().push(change.clone()));
+        let first = snapshot(vec![imola, monaco.clone()], "2023-05-28T15:00:00Z");
+        assert!(watcher.apply(first).is_empty());
+        let second = snapshot(vec![monaco], "2023-05-28T16:00:00Z");
+        assert_eq!(watcher.apply(second).len(), 2);
+
+        let seen = seen.borrow();
+        assert!(
+            matches!(&seen[0], ScheduleChange::EventRemoved { event } if event.meeting_key == "1209")
+        );
+        match &seen[1] {
+            ScheduleChange::SessionRescheduled {
+                meeting_key,
+                session,
+                from,
+                to,
+            } => {
+                assert_eq!((meeting_key.as_str(), *session), ("1210", Sessions::Race));
+                assert_eq!(to.0 - from.0, Duration::hours(1));
+                assert_eq!(to.0, utc("2023-05-28T14:00:00Z"));
+            }
+            change => panic!("{change:?}"),
+        }
+    }
+
+    #[test]
+    fn schedule_watcher_keeps_polling_after_errors() {
+        use crate::{
+            clock::ManualClock,
+            watch::{ScheduleChange, ScheduleWatcher},
+        };
+        use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration as StdDuration};
+
+        let calendar = "editorial-eventlisting/events?season=2023";
+        let clock = Arc::new(ManualClock::new(utc("2023-07-09T12:00:00Z")));
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let mut watcher = ScheduleWatcher::new("2023");
+        let log = errors.clone();
+        watcher
+            .clock(clock.clone())
+            .on_error(move |e| log.borrow_mut().push(e.to_string()));
+
+        watcher.source(formula1(&[(calendar, json!({}))]));
+        watcher.run_until(utc("2023-07-09T12:10:00Z"), StdDuration::from_secs(300));
+        assert_eq!(*errors.borrow(), ["Key `events` not in received json."; 3]);
+        assert!(watcher.snapshot().is_none());
+
+        watcher.source(formula1(&[(calendar, json!({"events": []}))]));
+        watcher.run_until(utc("2023-07-09T12:15:00Z"), StdDuration::from_secs(300));
+        assert_eq!(errors.borrow().len(), 3);
+        assert!(watcher.snapshot().is_some());
+
+        // Its timetable can't be read, the event is kept without one.
+        let british = event(
+            "1214",
+            "British Grand Prix",
+            "Silverstone",
+            "Great Britain",
+            "ROUND 10",
+        );
+        watcher.source(formula1(&[(calendar, json!({ "events": [british] }))]));
+        let changes = watcher.poll().unwrap();
+        assert!(matches!(changes[..], [ScheduleChange::EventAdded { .. }]));
+        let snapshot = watcher.snapshot().unwrap();
+        assert_eq!(snapshot.events.len(), 1);
+        assert!(snapshot.timetables.is_empty());
+    }
+
+    #[test]
+    fn notifier_follows_a_session() {
+        use crate::notify::{Exec, Notification, Notifier, Sink};
+        use crate::upcoming::ScheduledSession;
+        use std::{cell::RefCell, rc::Rc};
+
+        struct Record(Rc<RefCell<Vec<String>>>);
+        impl Sink for Record {
+            fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>> {
+                self.0.borrow_mut().push(notification.message());
+                Ok(())
+            }
+        }
+
+        let race = ScheduledSession {
+            event: event(
+                "f1-10",
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            ),
+            timetable: Timetable {
+                state: Status::Upcoming,
+                session: Sessions::Race,
+                description: "Race".to_string(),
+                start_date: utc("2023-07-09T15:00:00Z"),
+                end_date: utc("2023-07-09T17:00:00Z"),
+                gmt_offset: "+01:00".to_string(),
+            },
+        };
+        let log = std::env::temp_dir().join(format!("f1-notify-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&log);
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let mut notifier = Notifier::new(vec![race])
+            .remind_before(Duration::minutes(60))
+            .remind_before(Duration::minutes(15))
+            .sink(Record(messages.clone()))
+            .sink(Exec::new(
+                "sh",
+                &["-c", &format!("cat >> {}", log.display())],
+            ))
+            .source(Fake {
+                prefix: "f1-",
+                results: true,
+            });
+
+        // Starts at 14:00 UTC.
+        for now in [
+            "12:00", "13:10", "13:20", "13:50", "13:55", "14:30", "16:05", "16:10",
+        ] {
+            notifier.tick(utc(&format!("2023-07-09T{now}:00Z")));
+        }
+        assert_eq!(
+            *messages.borrow(),
+            [
+                "British Grand Prix: Race starts in 50 minutes",
+                "British Grand Prix: Race starts in 10 minutes",
+                "British Grand Prix: Race has ended",
+                "British Grand Prix: Race results are in, P1 HAM",
+            ]
+        );
+        let json = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(json.matches(r#""type":"#).count(), 4);
+        assert!(json.contains(r#""type":"results_available""#));
+        std::fs::remove_file(&log).unwrap();
+    }
+
+    #[test]
+    fn notifier_retries_failed_deliveries() {
+        use crate::notify::{Notification, Notifier, Sink};
+        use crate::upcoming::ScheduledSession;
+        use std::{cell::RefCell, rc::Rc};
+
+        /// Fails the first `failures` sends.
+        struct Flaky {
+            failures: u32,
+            sent: Rc<RefCell<Vec<String>>>,
+        }
+        impl Sink for Flaky {
+            fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>> {
+                if self.failures > 0 {
+                    self.failures -= 1;
+                    return Err("hook unreachable".into());
+                }
+                self.sent.borrow_mut().push(notification.message());
+                Ok(())
+            }
+        }
+
+        let race = ScheduledSession {
+            event: event(
+                "f1-10",
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            ),
+            timetable: Timetable {
+                state: Status::Upcoming,
+                session: Sessions::Race,
+                description: "Race".to_string(),
+                start_date: utc("2023-07-09T15:00:00Z"),
+                end_date: utc("2023-07-09T17:00:00Z"),
+                gmt_offset: "+01:00".to_string(),
+            },
+        };
+        let (flaky, steady) = (
+            Rc::new(RefCell::new(Vec::new())),
+            Rc::new(RefCell::new(Vec::new())),
+        );
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let log = errors.clone();
+        let mut notifier = Notifier::new(vec![race])
+            .remind_before(Duration::minutes(15))
+            .sink(Flaky {
+                failures: 2,
+                sent: flaky.clone(),
+            })
+            .sink(Flaky {
+                failures: 0,
+                sent: steady.clone(),
+            })
+            .source(Fake {
+                prefix: "f1-",
+                results: false,
+            })
+            .on_error(move |notification, e| {
+                log.borrow_mut()
+                    .push(format!("{}: {e}", notification.kind()))
+            });
+
+        // Starts at 14:00 UTC, the reminder is due at the first tick.
+        for now in ["13:50", "13:51", "13:52"] {
+            notifier.tick(utc(&format!("2023-07-09T{now}:00Z")));
+        }
+        let reminder = ["British Grand Prix: Race starts in 10 minutes"];
+        assert_eq!(*steady.borrow(), reminder);
+        assert_eq!(*flaky.borrow(), reminder);
+        assert_eq!(*errors.borrow(), ["starting_soon: hook unreachable"; 2]);
+    }
+
+    #[test]
+    fn manual_clock_runs_a_weekend() {
+        use crate::clock::{Clock, ManualClock};
+        use crate::notify::Notifier;
+        use crate::upcoming::{phase_in, ScheduledSession, WeekendPhase};
+        use std::sync::Arc;
+
+        let session = |session, description: &str, start: &str, hours| ScheduledSession {
+            event: event(
+                "f1-10",
+                "British Grand Prix",
+                "Silverstone",
+                "Great Britain",
+                "ROUND 10",
+            ),
+            timetable: Timetable {
+                state: Status::Upcoming,
+                session,
+                description: description.to_string(),
+                start_date: utc(start),
+                end_date: utc(start) + Duration::hours(hours),
+                gmt_offset: "+01:00".to_string(),
+            },
+        };
+        let sessions = vec![
+            session(
+                Sessions::FreePractice1,
+                "Practice 1",
+                "2023-07-07T12:30:00Z",
+                1,
+            ),
+            session(
+                Sessions::Qualifying,
+                "Qualifying",
+                "2023-07-08T15:00:00Z",
+                1,
+            ),
+            session(Sessions::Race, "Race", "2023-07-09T15:00:00Z", 2),
+        ];
+        let timetables: Vec<Timetable> = sessions.iter().map(|s| s.timetable.clone()).collect();
+
+        let clock = Arc::new(ManualClock::new(utc("2023-07-07T08:00:00Z")));
+        assert_eq!(phase_in(&timetables, clock.now()), WeekendPhase::PreWeekend);
+        let mut notifier = Notifier::new(sessions)
+            .remind_before(Duration::minutes(30))
+            .source(Fake {
+                prefix: "f1-",
+                results: true,
+            })
+            .clock(clock.clone());
+        let interval = std::time::Duration::from_secs(5 * 60);
+
+        let friday = notifier.run_until(utc("2023-07-07T23:59:00Z"), interval);
+        assert_eq!(friday.len(), 3);
+        assert_eq!(phase_in(&timetables, clock.now()), WeekendPhase::Practice);
+
+        clock.set(utc("2023-07-08T12:00:00Z"));
+        let rest = notifier.run_until(utc("2023-07-09T20:00:00Z"), interval);
+        let kinds: Vec<&str> = rest.iter().map(|n| n.kind()).collect();
+        assert_eq!(
+            kinds,
+            ["starting_soon", "ended", "results_available"].repeat(2)
+        );
+        assert_eq!(
+            rest[3].message(),
+            "British Grand Prix: Race starts in 30 minutes"
+        );
+        assert!(clock.now() > utc("2023-07-09T20:00:00Z"));
+        assert_eq!(
+            phase_in(&timetables, clock.now()),
+            WeekendPhase::PostWeekend
+        );
+
+        // Done once the race (ending 16:00 UTC) is no longer followed up, a day later.
+        notifier.run(interval);
+        assert!(clock.now() > utc("2023-07-10T16:00:00Z"));
+        assert!(clock.now() <= utc("2023-07-10T16:05:00Z"));
+    }
+
+    #[test]
+    fn qualifying_eliminations() {
+        use crate::analysis::qualifying::{qualifying_report, Segment};
+
+        let results = vec![
+            quali_result("VER", "Red Bull", &["1:30.000", "1:29.800", "1:29.500"]),
+            quali_result("HAM", "Mercedes", &["1:30.500", "1:30.100", "1:29.700"]),
+            quali_result("PER", "Red Bull", &["1:30.800", "1:30.300"]),
+            quali_result("RUS", "Mercedes", &["1:31.000"]),
+        ];
+        let report = qualifying_report(&results);
+        assert_eq!(report.segments.len(), 3);
+        let [q1, q2, q3] = &report.segments[..] else {
+            panic!()
+        };
+        assert_eq!(q1.knocked_out, ["RUS"]);
+        assert_eq!(q1.cut_off_ms, Some(90_800));
+        assert_eq!(q1.ranking[0].margin_ms, Some(1_000));
+        assert_eq!(q1.ranking[3].margin_ms, Some(-200));
+        assert_eq!(q2.knocked_out, ["PER"]);
+        assert_eq!(q2.ranking[1].driver_tla, "HAM");
+        assert_eq!(q2.ranking[1].gap_to_fastest_ms, Some(300));
+        assert_eq!(q2.ranking[2].margin_ms, Some(-200));
+        assert!(q3.knocked_out.is_empty() && q3.cut_off_ms.is_none());
+
+        let red_bull = &report.teammate_gaps[0];
+        assert_eq!(
+            (red_bull.ahead.as_str(), red_bull.segment),
+            ("VER", Segment::Q2)
+        );
+        assert_eq!(red_bull.gap_ms, 500);
+        assert!((red_bull.gap_percent - 0.5567).abs() < 0.001);
+        assert_eq!(report.teammate_gaps[1].segment, Segment::Q1);
+        let ver: Vec<i64> = report
+            .improvements
+            .iter()
+            .filter(|i| i.driver_tla == "VER")
+            .map(|i| i.delta_ms)
+            .collect();
+        assert_eq!(ver, [-200, -300]);
+    }
+
+    #[test]
+    fn teammate_head_to_head() {
+        use crate::{analysis::head_to_head::head_to_head, results::CompletionStatusCode};
+
+        let sheet = |results| SessionResult {
+            state: Status::Completed,
+            session_info: None,
+            results,
+        };
+        let quali = |tla, position: &str, time| {
+            let mut result = quali_result(tla, "Mercedes", &[time]);
+            result.position_number = position.to_string();
+            result
+        };
+        let race = |tla, position, points, finished: bool| {
+            let mut result = race_result(tla, position);
+            result.race_points = points;
+            if !finished {
+                result.completion_status_code = CompletionStatusCode::Dnf;
+            }
+            result
+        };
+        let mut verstappen = race_result("VER", "2");
+        verstappen.team_name = "Red Bull".to_string();
+        let results = vec![
+            sheet(ResultSheet::Qualifying(vec![
+                quali("HAM", "1", "1:30.000"),
+                quali("RUS", "2", "1:30.900"),
+            ])),
+            sheet(ResultSheet::Race(vec![
+                race("HAM", "1", 25, true),
+                verstappen,
+                race("RUS", "3", 15, true),
+            ])),
+            sheet(ResultSheet::Qualifying(vec![
+                quali("RUS", "1", "1:29.820"),
+                quali("HAM", "2", "1:30.000"),
+            ])),
+            sheet(ResultSheet::Race(vec![
+                race("HAM", "", 0, false),
+                race("RUS", "", 0, false),
+            ])),
+            sheet(ResultSheet::Race(vec![
+                race("RUS", "5", 10, true),
+                race("HAM", "", 0, false),
+            ])),
+        ];
+        let head_to_heads = head_to_head(&results);
+        assert_eq!(head_to_heads.len(), 1);
+        let [ham, rus] = &head_to_heads[0].drivers;
+        assert_eq!(
+            (ham.driver_tla.as_str(), rus.driver_tla.as_str()),
+            ("HAM", "RUS")
+        );
+        assert_eq!((ham.qualifying, rus.qualifying), (1, 1));
+        assert_eq!((ham.race, rus.race), (1, 1));
+        assert_eq!((ham.points, rus.points), (25, 25));
+        assert_eq!(ham.points_share, 0.5);
+        let gap = head_to_heads[0].qualifying_gap_percent.unwrap();
+        assert!((gap - 0.3998).abs() < 0.001);
+    }
+
+    #[test]
+    fn grid_to_finish_with_penalties() {
+        use crate::{
+            analysis::grid::{grid_from_qualifying, grid_to_finish, GridPenalty},
+            ergast::LapTime,
+            results::CompletionStatusCode,
+        };
+
+        let qualifying: Vec<DriverQualifyingResult> = ["HAM", "RUS", "VER", "PER"]
+            .iter()
+            .enumerate()
+            .map(|(i, tla)| {
+                let mut result = quali_result(tla, "", &["1:30.000"]);
+                result.position_number = (i + 1).to_string();
+                result
+            })
+            .collect();
+        let penalties = [
+            ("HAM", GridPenalty::Places(2)),
+            ("PER", GridPenalty::PitLane),
+        ];
+        let grid = grid_from_qualifying(&qualifying, &penalties);
+        let order: Vec<(&str, Option<u32>)> = grid
+            .iter()
+            .map(|s| (s.driver_tla.as_str(), s.position))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("RUS", Some(1)),
+                ("VER", Some(2)),
+                ("HAM", Some(3)),
+                ("PER", None)
+            ]
+        );
+
+        let unclassified = |tla, position| {
+            let mut result = race_result(tla, position);
+            result.completion_status_code = CompletionStatusCode::Dnf;
+            result
+        };
+        let race = [
+            race_result("VER", "1"),
+            race_result("HAM", "2"),
+            race_result("RUS", "3"),
+            unclassified("PER", "NC"),
+            // Disqualified after the flag, and a non-starter.
+            unclassified("ALO", "DQ"),
+            unclassified("SAR", "NC"),
+            // Classified, but missing from the grid.
+            race_result("BOT", "4"),
+        ];
+        let lap = |driver_tla: &str, lap, position| LapTime {
+            driver_tla: driver_tla.to_string(),
+            lap,
+            position,
+            time_ms: 90_000,
+        };
+        let laps = [
+            lap("VER", 1, 1),
+            lap("RUS", 1, 2),
+            lap("HAM", 1, 3),
+            lap("PER", 1, 4),
+            lap("ALO", 1, 5),
+            lap("VER", 2, 1),
+            lap("HAM", 2, 2),
+            lap("RUS", 2, 3),
+            lap("ALO", 2, 4),
+        ];
+        let report = grid_to_finish(&grid, &race, &laps);
+        let gained: Vec<(Option<i32>, Option<u32>)> = report
+            .iter()
+            .map(|r| (r.positions_gained, r.overtakes))
+            .collect();
+        assert_eq!(
+            gained,
+            [
+                (Some(1), Some(1)),
+                (Some(1), Some(1)),
+                (Some(-2), Some(0)),
+                (None, Some(0)),
+                (None, None),
+                (None, None),
+                (None, None)
+            ]
+        );
+        let retired: Vec<Option<u32>> = report.iter().map(|r| r.retired_on_lap).collect();
+        assert_eq!(retired, [None, None, None, Some(2), None, None, None]);
+
+        let body = br#"{"MRData": {"total": "2", "RaceTable": {"Races": [{
+            "season": "2023", "round": "10", "raceName": "British Grand Prix",
+            "Circuit": {"circuitId": "silverstone", "circuitName": "Silverstone",
+                "Location": {"locality": "Silverstone", "country": "UK"}},
+            "date": "2023-07-09",
+            "Results": [
+                {"number": "1", "position": "1", "grid": "0",
+                 "Driver": {"driverId": "max_verstappen", "code": "VER",
+                    "givenName": "Max", "familyName": "Verstappen"},
+                 "Constructor": {"constructorId": "red_bull", "name": "Red Bull"}},
+                {"number": "4", "position": "2", "grid": "2",
+                 "Driver": {"driverId": "norris", "code": "NOR",
+                    "givenName": "Lando", "familyName": "Norris"},
+                 "Constructor": {"constructorId": "mclaren", "name": "McLaren"}}
+            ]}]}}}"#;
+        let ergast = crate::ergast::Ergast::with_base_url(serve(1, body));
+        let grid =
+            crate::source::DataSource::starting_grid(&ergast, &Sessions::Race, "2023-10").unwrap();
+        assert_eq!(
+            (grid[0].driver_tla.as_str(), grid[0].position),
+            ("NOR", Some(2))
+        );
+        assert_eq!(
+            (grid[1].driver_tla.as_str(), grid[1].position),
+            ("VER", None)
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn server_handles_requests() {
+        use crate::{clock::ManualClock, server::handle};
+
+        let silverstone = event(
+            "1214",
+            "British Grand Prix",
+            "Silverstone",
+            "Great Britain",
+            "ROUND 10",
+        );
+        let f1 = formula1(&[
+            (
+                "editorial-eventlisting/events?season=2023",
+                json!({ "events": [silverstone] }),
+            ),
+            (
+                "fom-results/timetables?meeting=1214",
+                json!({"timetables": [{"state": "upcoming", "session": "r",
+                    "description": "Race", "startTime": "2023-07-09T15:00:00",
+                    "endTime": "2023-07-09T17:00:00", "gmtOffset": "+01:00"}]}),
+            ),
+        ]);
+        let clock = ManualClock::new(utc("2023-07-08T12:00:00Z"));
+        let (status, body) = handle(&f1, &clock, "GET", "/v1/events/1214/timetable");
+        assert_eq!(status, 200);
+        let sessions: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(sessions[0]["session"], "race");
+        assert_eq!(sessions[0]["start_utc"], "2023-07-09T14:00:00Z");
+
+        let (status, body) = handle(&f1, &clock, "GET", "/v1/events/1214/results/warmup");
+        assert_eq!(status, 400);
+        assert_eq!(body, r#"{"error":"Unknown session `warmup`."}"#);
+        let (status, body) = handle(
+            &f1,
+            &clock,
+            "GET",
+            "/v1/events/1214/results/sprint_shootout",
+        );
+        assert_eq!(status, 400);
+        assert_eq!(
+            body,
+            r#"{"error":"Sprint shootout results aren't supported yet."}"#
+        );
+        assert_eq!(handle(&f1, &clock, "GET", "/v2/calendar/2023").0, 404);
+        assert_eq!(handle(&f1, &clock, "POST", "/v1/calendar/2023").0, 405);
+
+        let (status, body) = handle(&f1, &clock, "GET", "/v1/weekend/current");
+        assert_eq!(status, 200);
+        let weekend: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(weekend["meeting_key"], "1214");
+        clock.set(utc("2023-07-10T12:00:00Z"));
+        assert_eq!(handle(&f1, &clock, "GET", "/v1/weekend/current").0, 502);
+    }
 }
@@ -0,0 +1,173 @@
+//! Finds events from what people type: "silverstone", "British GP", "GBR", "Great Britain".
+//! Names, locations and countries are compared after folding case and accents, with aliases
+//! for country codes and circuit nicknames and some tolerance for typos.
+
+use std::error::Error;
+
+use crate::{ids::Season, schedule::Event};
+
+/// Below this an event isn't a candidate.
+const MIN_SCORE: f64 = 0.6;
+
+/// Country codes and nicknames, to what the calendar calls them.
+const ALIASES: &[(&str, &str)] = &[
+    ("gbr", "great britain"),
+    ("uk", "great britain"),
+    ("england", "great britain"),
+    ("usa", "united states"),
+    ("us", "united states"),
+    ("cota", "austin"),
+    ("vegas", "las vegas"),
+    ("uae", "abu dhabi"),
+    ("yas marina", "abu dhabi"),
+    ("ksa", "saudi arabia"),
+    ("sau", "saudi arabia"),
+    ("bhr", "bahrain"),
+    ("aus", "australia"),
+    ("albert park", "melbourne"),
+    ("aut", "austria"),
+    ("red bull ring", "spielberg"),
+    ("aze", "azerbaijan"),
+    ("bel", "belgium"),
+    ("spa", "belgium"),
+    ("bra", "brazil"),
+    ("interlagos", "sao paulo"),
+    ("can", "canada"),
+    ("chn", "china"),
+    ("esp", "spain"),
+    ("fra", "france"),
+    ("ger", "germany"),
+    ("hun", "hungary"),
+    ("hungaroring", "hungary"),
+    ("ita", "italy"),
+    ("imola", "emilia romagna"),
+    ("jpn", "japan"),
+    ("mex", "mexico"),
+    ("mon", "monaco"),
+    ("ned", "netherlands"),
+    ("nld", "netherlands"),
+    ("holland", "netherlands"),
+    ("qat", "qatar"),
+    ("losail", "qatar"),
+    ("sgp", "singapore"),
+    ("marina bay", "singapore"),
+];
+
+/// An event matching a query, higher scores are better matches (at most 1).
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub event: &'a Event,
+    pub score: f64,
+}
+
+/// Events of `events` matching `query`, best first.
+pub fn search<'a>(events: &'a [Event], query: &str) -> Vec<Candidate<'a>> {
+    let query = normalize(query);
+    let alias = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == query)
+        .map(|(_, name)| name.to_string());
+    let mut candidates: Vec<Candidate> = events
+        .iter()
+        .filter_map(|event| {
+            let fields = [
+                &event.meeting_name,
+                &event.meeting_location,
+                &event.meeting_country_name,
+                &event.meeting_official_name,
+            ]
+            .map(|field| normalize(field));
+            let best = |query: &str| {
+                fields
+                    .iter()
+                    .map(|field| score(query, field))
+                    .fold(0.0, f64::max)
+            };
+            let score = match &alias {
+                Some(alias) => best(&query).max(0.95 * best(alias)),
+                None => best(&query),
+            };
+            (score >= MIN_SCORE).then_some(Candidate { event, score })
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.event.meeting_start_date.cmp(&b.event.meeting_start_date))
+    });
+    candidates
+}
+
+/// Events of a season matching `query`, best first.
+pub fn search_season(query: &str, season: impl Into<Season>) -> Result<Vec<Event>, Box<dyn Error>> {
+    let calendar = season.into().calendar()?;
+    Ok(search(&calendar, query)
+        .into_iter()
+        .map(|c| c.event.clone())
+        .collect())
+}
+
+/// Lowercase ascii words, `gp` spelled out.
+fn normalize(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            c if c.is_ascii_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded
+        .split_whitespace()
+        .map(|word| if word == "gp" { "grand prix" } else { word })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn score(query: &str, field: &str) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    if query == field {
+        return 1.0;
+    }
+    let words: Vec<&str> = field.split_whitespace().collect();
+    let query_words: Vec<&str> = query.split_whitespace().collect();
+    if query_words.iter().all(|w| words.contains(w)) {
+        return 0.9;
+    }
+    if query.len() >= 3 && field.contains(query) {
+        return 0.8;
+    }
+    // Typos, against the whole field and runs of as many words as the query has.
+    let runs = words
+        .windows(query_words.len().min(words.len()).max(1))
+        .map(|run| similarity(query, &run.join(" ")));
+    0.85 * runs.fold(similarity(query, field), f64::max)
+}
+
+/// 1 minus the edit distance relative to the longer text.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
@@ -0,0 +1,349 @@
+//! Notifications around sessions: reminders before the start, the end, and once results are
+//! in (the sheet's state turned [`Status::Completed`]). [`Notifier::tick`] takes the time, so
+//! a weekend can be played through without waiting for it.
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    results::ResultRow,
+    schedule::{get_calendar, Event, Timetable},
+    source::{DataSource, Formula1},
+    upcoming::{sessions_of, ScheduledSession},
+    weekend_common::{Sessions, Status},
+};
+
+/// Ended sessions are followed up for this long, results that take longer are missed.
+const FOLLOW_UP: i64 = 24;
+
+/// Tries a sink gets per notification, failed ones are retried on the next ticks.
+const ATTEMPTS: u32 = 5;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    StartingSoon {
+        event: Event,
+        session: Timetable,
+        minutes: i64,
+    },
+    Ended {
+        event: Event,
+        session: Timetable,
+    },
+    ResultsAvailable {
+        event: Event,
+        session: Timetable,
+        results: Vec<ResultRow>,
+    },
+}
+
+impl Notification {
+    /// `starting_soon`, `ended` or `results_available`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Notification::StartingSoon { .. } => "starting_soon",
+            Notification::Ended { .. } => "ended",
+            Notification::ResultsAvailable { .. } => "results_available",
+        }
+    }
+
+    /// One line for people, like `British Grand Prix: Race starts in 15 minutes`.
+    pub fn message(&self) -> String {
+        match self {
+            Notification::StartingSoon {
+                event,
+                session,
+                minutes,
+            } => format!(
+                "{}: {} starts in {minutes} minutes",
+                event.meeting_name, session.description
+            ),
+            Notification::Ended { event, session } => {
+                format!("{}: {} has ended", event.meeting_name, session.description)
+            }
+            Notification::ResultsAvailable {
+                event,
+                session,
+                results,
+            } => {
+                let winner = results
+                    .iter()
+                    .find(|r| r.position == Some(1))
+                    .map(|r| format!(", P1 {}", r.driver_tla))
+                    .unwrap_or_default();
+                format!(
+                    "{}: {} results are in{winner}",
+                    event.meeting_name, session.description
+                )
+            }
+        }
+    }
+}
+
+/// Where notifications go.
+pub trait Sink {
+    fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>>;
+}
+
+/// Prints [`Notification::message`].
+pub struct Stdout;
+
+impl Sink for Stdout {
+    fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>> {
+        println!("{}", notification.message());
+        Ok(())
+    }
+}
+
+/// POSTs the notification as json.
+pub struct Webhook {
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Self {
+        Webhook {
+            url: url.to_string(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl Sink for Webhook {
+    fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>> {
+        self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(notification)?)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Runs a program with the notification as json on stdin, and `F1_NOTIFICATION` (the kind)
+/// and `F1_MESSAGE` in its environment.
+pub struct Exec {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Exec {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Exec {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+}
+
+impl Sink for Exec {
+    fn send(&mut self, notification: &Notification) -> Result<(), Box<dyn Error>> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env("F1_NOTIFICATION", notification.kind())
+            .env("F1_MESSAGE", notification.message())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(serde_json::to_string(notification)?.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("`{}` failed with {status}.", self.program).into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Fired {
+    Reminder(i64),
+    Ended,
+    Results,
+}
+
+/// A notification a sink still has to take.
+struct Retry {
+    sink: usize,
+    notification: Notification,
+    attempts: u32,
+}
+
+pub type ErrorCallback = Box<dyn FnMut(&Notification, &dyn Error)>;
+
+/// Sends notifications for the sessions it's given, each once.
+pub struct Notifier {
+    sessions: Vec<ScheduledSession>,
+    reminders: Vec<Duration>,
+    sinks: Vec<Box<dyn Sink>>,
+    source: Box<dyn DataSource>,
+    clock: Box<dyn Clock>,
+    fired: HashSet<(String, Sessions, Fired)>,
+    retries: Vec<Retry>,
+    error_callbacks: Vec<ErrorCallback>,
+}
+
+impl Notifier {
+    /// Results come from formula1.com, see [`Notifier::source`], time from the system clock.
+    pub fn new(sessions: Vec<ScheduledSession>) -> Self {
+        Notifier {
+            sessions,
+            reminders: Vec::new(),
+            sinks: Vec::new(),
+            source: Box::new(Formula1::new()),
+            clock: Box::new(SystemClock),
+            fired: HashSet::new(),
+            retries: Vec::new(),
+            error_callbacks: Vec::new(),
+        }
+    }
+
+    /// Every session of a season.
+    pub fn from_calendar(year: &str) -> Result<Self, Box<dyn Error>> {
+        let mut sessions = Vec::new();
+        for event in get_calendar(year)? {
+            sessions.extend(sessions_of(&event)?);
+        }
+        Ok(Notifier::new(sessions))
+    }
+
+    /// Adds a reminder this long before every start.
+    pub fn remind_before(mut self, before: Duration) -> Self {
+        self.reminders.push(before);
+        self
+    }
+
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Where results are checked.
+    pub fn source(mut self, source: impl DataSource + 'static) -> Self {
+        self.source = Box::new(source);
+        self
+    }
+
+    /// What [`Notifier::run`] takes the time from.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Failed deliveries go here, without callbacks they're only retried.
+    pub fn on_error(mut self, callback: impl FnMut(&Notification, &dyn Error) + 'static) -> Self {
+        self.error_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Sends what's due at `now` and returns it. Reminders of sessions that already started
+    /// aren't sent, so starting mid-weekend doesn't replay the past. A sink that fails gets the
+    /// notification again on the next ticks, up to [`ATTEMPTS`] tries in all.
+    pub fn tick(&mut self, now: DateTime<Utc>) -> Vec<Notification> {
+        for retry in std::mem::take(&mut self.retries) {
+            self.deliver(retry.sink, retry.notification, retry.attempts);
+        }
+        let mut due = Vec::new();
+        for scheduled in &self.sessions {
+            let (event, session) = (&scheduled.event, &scheduled.timetable);
+            let (start, end) = (session.start_utc(), session.end_utc());
+            let mut fire = |what: Fired| {
+                self.fired
+                    .insert((event.meeting_key.clone(), session.session, what))
+            };
+            for before in &self.reminders {
+                if start - *before <= now
+                    && now < start
+                    && fire(Fired::Reminder(before.num_minutes()))
+                {
+                    due.push(Notification::StartingSoon {
+                        event: event.clone(),
+                        session: session.clone(),
+                        minutes: (start - now).num_minutes().max(1),
+                    });
+                }
+            }
+            if now < end || now >= end + Duration::hours(FOLLOW_UP) {
+                continue;
+            }
+            if fire(Fired::Ended) {
+                due.push(Notification::Ended {
+                    event: event.clone(),
+                    session: session.clone(),
+                });
+            }
+            let key = (event.meeting_key.clone(), session.session, Fired::Results);
+            if self.fired.contains(&key) {
+                continue;
+            }
+            // Not there yet, or no sheet for this session.
+            let Ok(result) = self.source.results(&session.session, &event.meeting_key) else {
+                continue;
+            };
+            if result.state == Status::Completed {
+                self.fired.insert(key);
+                due.push(Notification::ResultsAvailable {
+                    event: event.clone(),
+                    session: session.clone(),
+                    results: result.results.rows(),
+                });
+            }
+        }
+        for notification in &due {
+            for sink in 0..self.sinks.len() {
+                self.deliver(sink, notification.clone(), 0);
+            }
+        }
+        due
+    }
+
+    fn deliver(&mut self, sink: usize, notification: Notification, attempts: u32) {
+        let Err(e) = self.sinks[sink].send(&notification) else {
+            return;
+        };
+        for callback in &mut self.error_callbacks {
+            callback(&notification, e.as_ref());
+        }
+        if attempts + 1 < ATTEMPTS {
+            self.retries.push(Retry {
+                sink,
+                notification,
+                attempts: attempts + 1,
+            });
+        }
+    }
+
+    /// Ticks every `interval` until the last session is no longer followed up, [`FOLLOW_UP`]
+    /// hours after it ended.
+    pub fn run(&mut self, interval: std::time::Duration) {
+        let last_end = self.sessions.iter().map(|s| s.timetable.end_utc()).max();
+        if let Some(last_end) = last_end {
+            self.run_until(last_end + Duration::hours(FOLLOW_UP), interval);
+        }
+    }
+
+    /// Ticks every `interval` until the clock passes `until`, returns what was sent.
+    pub fn run_until(
+        &mut self,
+        until: DateTime<Utc>,
+        interval: std::time::Duration,
+    ) -> Vec<Notification> {
+        let mut sent = Vec::new();
+        while self.clock.now() <= until {
+            sent.extend(self.tick(self.clock.now()));
+            self.clock.sleep(interval);
+        }
+        sent
+    }
+}
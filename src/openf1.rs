@@ -0,0 +1,836 @@
+//! Backend for the OpenF1 api, historical telemetry (from 2023 on) without a recording of the
+//! live feed. Meeting and session keys are OpenF1's numeric keys, like `1214` and `9158`.
+
+use std::{collections::HashMap, error::Error, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    results::{
+        Classifying, CompletionStatusCode, DriverQualifyingResult, PracticeResult, RaceResult,
+        ResultSheet, SessionResult, SprintResult,
+    },
+    schedule::{Event, Timetable},
+    source::{find_driver, DataSource},
+    standings::{compute_standings, Standings},
+    telemetry::{
+        car_data::CarDataSample,
+        driver_list::Driver,
+        position::{PositionSample, PositionStatus},
+        race_control::RaceControlMessage,
+        stints::{DriverStrategy, PitStop, RaceStrategy, Stint},
+        team_radio::TeamRadioCapture,
+        weather::WeatherSample,
+    },
+    utils::get_text,
+    weekend_common::{parse_gmt_offset, whole_points, Sessions, Status},
+};
+
+pub const OPENF1_URL: &str = "https://api.openf1.org/v1/";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Meeting {
+    pub meeting_key: u32,
+    pub meeting_name: String,
+    pub meeting_official_name: String,
+    pub country_name: String,
+    pub location: String,
+    pub circuit_short_name: String,
+    pub date_start: DateTime<Utc>,
+    /// Like `01:00:00` or `-04:00:00`.
+    pub gmt_offset: String,
+    pub year: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session {
+    pub session_key: u32,
+    pub meeting_key: u32,
+    /// `Practice 1`, `Qualifying`, `Sprint`, `Race`, `Day 1` (testing), ...
+    pub session_name: String,
+    pub session_type: String,
+    pub date_start: DateTime<Utc>,
+    pub date_end: DateTime<Utc>,
+    pub gmt_offset: String,
+    pub year: u32,
+}
+
+impl Session {
+    /// `None` for testing days.
+    pub fn session(&self) -> Option<Sessions> {
+        Some(match self.session_name.as_str() {
+            "Race" => Sessions::Race,
+            "Sprint" => Sessions::Sprint,
+            "Qualifying" => Sessions::Qualifying,
+            "Sprint Shootout" | "Sprint Qualifying" => Sessions::SprintShootout,
+            "Practice 1" => Sessions::FreePractice1,
+            "Practice 2" => Sessions::FreePractice2,
+            "Practice 3" => Sessions::FreePractice3,
+            _ => return None,
+        })
+    }
+
+    /// Completed when it ended by `now`.
+    fn timetable(&self, now: DateTime<Utc>) -> Option<Timetable> {
+        let (gmt_offset, offset) = parse_offset(&self.gmt_offset);
+        Some(Timetable {
+            state: status(self.date_end, now),
+            session: self.session()?,
+            description: self.session_name.clone(),
+            start_date: self.date_start + offset,
+            end_date: self.date_end + offset,
+            gmt_offset,
+        })
+    }
+}
+
+/// A lap of a driver, sector times and lap duration in seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Lap {
+    pub driver_number: u32,
+    pub lap_number: u32,
+    pub date_start: Option<DateTime<Utc>>,
+    pub lap_duration: Option<f64>,
+    pub duration_sector_1: Option<f64>,
+    pub duration_sector_2: Option<f64>,
+    pub duration_sector_3: Option<f64>,
+    #[serde(default)]
+    pub is_pit_out_lap: bool,
+    /// km/h at the speed trap.
+    pub st_speed: Option<u32>,
+}
+
+/// Start of every lap of a driver, for [`crate::telemetry::trace::split_laps`].
+pub fn lap_starts(laps: &[Lap]) -> Vec<DateTime<Utc>> {
+    let mut starts: Vec<DateTime<Utc>> = laps.iter().filter_map(|l| l.date_start).collect();
+    starts.sort();
+    starts
+}
+
+/// Gaps as the timing screens show them, `+12.345` or `+1 LAP`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub utc: DateTime<Utc>,
+    pub racing_number: String,
+    pub gap_to_leader: Option<String>,
+    pub interval: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawDriver {
+    driver_number: u32,
+    name_acronym: Option<String>,
+    full_name: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    team_name: Option<String>,
+    team_colour: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCarData {
+    date: DateTime<Utc>,
+    rpm: u32,
+    speed: u32,
+    n_gear: u8,
+    throttle: u8,
+    brake: u8,
+    drs: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct RawLocation {
+    date: DateTime<Utc>,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Deserialize)]
+struct RawInterval {
+    date: DateTime<Utc>,
+    driver_number: u32,
+    gap_to_leader: Value,
+    interval: Value,
+}
+
+#[derive(Deserialize)]
+struct RawPit {
+    driver_number: u32,
+    lap_number: u32,
+    pit_duration: Option<f64>,
+    stop_duration: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct RawStint {
+    driver_number: u32,
+    stint_number: u32,
+    lap_start: Option<u32>,
+    lap_end: Option<u32>,
+    compound: Option<String>,
+    tyre_age_at_start: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct RawRaceControl {
+    date: DateTime<Utc>,
+    category: String,
+    flag: Option<String>,
+    scope: Option<String>,
+    sector: Option<u32>,
+    driver_number: Option<u32>,
+    lap_number: Option<u32>,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RawWeather {
+    date: DateTime<Utc>,
+    air_temperature: f64,
+    track_temperature: f64,
+    humidity: f64,
+    pressure: f64,
+    rainfall: f64,
+    wind_direction: f64,
+    wind_speed: f64,
+}
+
+#[derive(Deserialize)]
+struct RawTeamRadio {
+    date: DateTime<Utc>,
+    driver_number: u32,
+    recording_url: String,
+}
+
+#[derive(Deserialize)]
+struct RawSessionResult {
+    position: Option<u32>,
+    driver_number: u32,
+    #[serde(default)]
+    points: Option<f64>,
+    #[serde(default)]
+    dnf: bool,
+    #[serde(default)]
+    dns: bool,
+    #[serde(default)]
+    dsq: bool,
+    /// Seconds, qualifying has one per segment.
+    duration: Value,
+    gap_to_leader: Value,
+}
+
+/// `01:00:00` to `+01:00` and the offset itself.
+fn parse_offset(gmt_offset: &str) -> (String, Duration) {
+    let offset = parse_gmt_offset(gmt_offset);
+    let minutes = offset.num_minutes();
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let text = format!("{sign}{:02}:{:02}", minutes.abs() / 60, minutes.abs() % 60);
+    (text, offset)
+}
+
+fn status(end: DateTime<Utc>, now: DateTime<Utc>) -> Status {
+    if end <= now {
+        Status::Completed
+    } else {
+        Status::Upcoming
+    }
+}
+
+/// Seconds to `1:27:38.241` or `1:30.123`, like the formula1.com results.
+fn format_seconds(seconds: f64) -> String {
+    let ms = (seconds * 1000.0).round() as u64;
+    let (hours, minutes, seconds, ms) =
+        (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}.{ms:03}")
+    } else {
+        format!("{minutes}:{seconds:02}.{ms:03}")
+    }
+}
+
+/// Gaps come as seconds, or as text for lapped cars.
+fn gap(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(format!("+{:.3}", n.as_f64()?)),
+        Value::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Client of the OpenF1 api. Responses go through the cache of
+/// [`crate::utils::enable_cache`].
+#[derive(Clone)]
+pub struct OpenF1 {
+    base_url: String,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+impl std::fmt::Debug for OpenF1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenF1")
+            .field("base_url", &self.base_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for OpenF1 {
+    fn default() -> Self {
+        OpenF1::with_base_url(OPENF1_URL)
+    }
+}
+
+impl OpenF1 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `base_url` is the part before `sessions?...`.
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        OpenF1 {
+            base_url,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// What tells completed sessions from upcoming ones, the system clock by default.
+    pub fn clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, u32)],
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let query: Vec<String> = query.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let url = format!("{}{endpoint}?{}", self.base_url, query.join("&"));
+        Ok(serde_json::from_str(&get_text(&url)?)?)
+    }
+
+    pub fn meetings(&self, year: u32) -> Result<Vec<Meeting>, Box<dyn Error>> {
+        self.get("meetings", &[("year", year)])
+    }
+
+    pub fn sessions(&self, meeting_key: u32) -> Result<Vec<Session>, Box<dyn Error>> {
+        self.get("sessions", &[("meeting_key", meeting_key)])
+    }
+
+    pub fn sessions_of_year(&self, year: u32) -> Result<Vec<Session>, Box<dyn Error>> {
+        self.get("sessions", &[("year", year)])
+    }
+
+    /// The session of a meeting, like the race of meeting `1214`.
+    pub fn session(&self, meeting_key: u32, session: Sessions) -> Result<Session, Box<dyn Error>> {
+        self.sessions(meeting_key)?
+            .into_iter()
+            .find(|s| s.session() == Some(session))
+            .ok_or_else(|| format!("Meeting {meeting_key} has no {}.", session.name()).into())
+    }
+
+    pub fn drivers(&self, session_key: u32) -> Result<Vec<Driver>, Box<dyn Error>> {
+        let drivers: Vec<RawDriver> = self.get("drivers", &[("session_key", session_key)])?;
+        Ok(drivers
+            .into_iter()
+            .map(|d| Driver {
+                racing_number: d.driver_number.to_string(),
+                tla: d.name_acronym.unwrap_or_default(),
+                full_name: d.full_name.unwrap_or_default(),
+                team_name: d.team_name.unwrap_or_default(),
+                team_colour: d.team_colour.unwrap_or_default(),
+                line: 0,
+            })
+            .collect())
+    }
+
+    /// Laps of every driver, or of one.
+    pub fn laps(&self, session_key: u32, driver: Option<u32>) -> Result<Vec<Lap>, Box<dyn Error>> {
+        let mut query = vec![("session_key", session_key)];
+        query.extend(driver.map(|d| ("driver_number", d)));
+        self.get("laps", &query)
+    }
+
+    /// Car data of a driver, at about 4 Hz like the live feed.
+    pub fn car_data(
+        &self,
+        session_key: u32,
+        driver: u32,
+    ) -> Result<Vec<CarDataSample>, Box<dyn Error>> {
+        let samples: Vec<RawCarData> = self.get(
+            "car_data",
+            &[("session_key", session_key), ("driver_number", driver)],
+        )?;
+        Ok(samples
+            .into_iter()
+            .map(|s| CarDataSample {
+                utc: s.date,
+                rpm: s.rpm,
+                speed: s.speed,
+                gear: s.n_gear,
+                throttle: s.throttle,
+                brake: s.brake,
+                drs: s.drs.unwrap_or(0),
+            })
+            .collect())
+    }
+
+    /// Positions of a driver. OpenF1 doesn't say when a car is off track, every sample is
+    /// [`PositionStatus::OnTrack`].
+    pub fn location(
+        &self,
+        session_key: u32,
+        driver: u32,
+    ) -> Result<Vec<PositionSample>, Box<dyn Error>> {
+        let samples: Vec<RawLocation> = self.get(
+            "location",
+            &[("session_key", session_key), ("driver_number", driver)],
+        )?;
+        Ok(samples
+            .into_iter()
+            .map(|s| PositionSample {
+                utc: s.date,
+                status: PositionStatus::OnTrack,
+                x: s.x,
+                y: s.y,
+                z: s.z,
+            })
+            .collect())
+    }
+
+    pub fn intervals(&self, session_key: u32) -> Result<Vec<Interval>, Box<dyn Error>> {
+        let intervals: Vec<RawInterval> = self.get("intervals", &[("session_key", session_key)])?;
+        Ok(intervals
+            .into_iter()
+            .map(|i| Interval {
+                utc: i.date,
+                racing_number: i.driver_number.to_string(),
+                gap_to_leader: gap(&i.gap_to_leader),
+                interval: gap(&i.interval),
+            })
+            .collect())
+    }
+
+    /// Stints and pit stops of every driver, from the `stints`, `pit` and `drivers` endpoints.
+    pub fn strategy(&self, session_key: u32) -> Result<RaceStrategy, Box<dyn Error>> {
+        let query = [("session_key", session_key)];
+        let mut stints: Vec<RawStint> = self.get("stints", &query)?;
+        let pits: Vec<RawPit> = self.get("pit", &query)?;
+        let tlas: HashMap<String, String> = self
+            .drivers(session_key)?
+            .into_iter()
+            .map(|d| (d.racing_number, d.tla))
+            .collect();
+        stints.sort_by_key(|s| (s.driver_number, s.stint_number));
+
+        let mut drivers: Vec<DriverStrategy> = Vec::new();
+        let mut driver = |number: u32| -> usize {
+            let number = number.to_string();
+            match drivers.iter().position(|d| d.racing_number == number) {
+                Some(i) => i,
+                None => {
+                    drivers.push(DriverStrategy {
+                        driver_tla: tlas.get(&number).cloned(),
+                        racing_number: number,
+                        stints: Vec::new(),
+                        pit_stops: Vec::new(),
+                    });
+                    drivers.len() - 1
+                }
+            }
+        };
+        let mut indices = Vec::new();
+        for stint in &stints {
+            indices.push(driver(stint.driver_number));
+        }
+        for pit in &pits {
+            indices.push(driver(pit.driver_number));
+        }
+        let (stint_indices, pit_indices) = indices.split_at(stints.len());
+        for (stint, &i) in stints.into_iter().zip(stint_indices) {
+            let tyre_age_at_start = stint.tyre_age_at_start.unwrap_or(0);
+            let start_lap = stint.lap_start.unwrap_or(1);
+            drivers[i].stints.push(Stint {
+                compound: serde_json::from_value(Value::from(stint.compound.unwrap_or_default()))?,
+                new: tyre_age_at_start == 0,
+                start_lap,
+                end_lap: stint.lap_end.unwrap_or(start_lap),
+                tyre_age_at_start,
+            });
+        }
+        for (pit, &i) in pits.into_iter().zip(pit_indices) {
+            drivers[i].pit_stops.push(PitStop {
+                in_lap: pit.lap_number,
+                out_lap: pit.lap_number + 1,
+                pit_lane_time: pit.pit_duration,
+                stationary_time: pit.stop_duration,
+            });
+        }
+        drivers.sort_by_key(|d| d.racing_number.parse::<u32>().unwrap_or(u32::MAX));
+        Ok(RaceStrategy { drivers })
+    }
+
+    pub fn race_control(
+        &self,
+        session_key: u32,
+    ) -> Result<Vec<RaceControlMessage>, Box<dyn Error>> {
+        let messages: Vec<RawRaceControl> =
+            self.get("race_control", &[("session_key", session_key)])?;
+        messages
+            .into_iter()
+            .map(|m| {
+                Ok(RaceControlMessage {
+                    utc: m.date,
+                    lap: m.lap_number,
+                    category: serde_json::from_value(Value::from(m.category))?,
+                    flag: m
+                        .flag
+                        .map(|f| serde_json::from_value(Value::from(f)))
+                        .transpose()?,
+                    scope: m
+                        .scope
+                        .map(|s| serde_json::from_value(Value::from(s)))
+                        .transpose()?,
+                    sector: m.sector,
+                    racing_number: m.driver_number.map(|n| n.to_string()),
+                    status: None,
+                    mode: None,
+                    message: m.message,
+                })
+            })
+            .collect()
+    }
+
+    pub fn weather(&self, session_key: u32) -> Result<Vec<WeatherSample>, Box<dyn Error>> {
+        let samples: Vec<RawWeather> = self.get("weather", &[("session_key", session_key)])?;
+        Ok(samples
+            .into_iter()
+            .map(|w| WeatherSample {
+                utc: w.date,
+                air_temp: w.air_temperature,
+                track_temp: w.track_temperature,
+                humidity: w.humidity,
+                pressure: w.pressure,
+                rainfall: w.rainfall,
+                wind_direction: w.wind_direction,
+                wind_speed: w.wind_speed,
+            })
+            .collect())
+    }
+
+    /// Captures with the full url of the clip as `path`.
+    pub fn team_radio(&self, session_key: u32) -> Result<Vec<TeamRadioCapture>, Box<dyn Error>> {
+        let captures: Vec<RawTeamRadio> =
+            self.get("team_radio", &[("session_key", session_key)])?;
+        Ok(captures
+            .into_iter()
+            .map(|c| TeamRadioCapture {
+                utc: c.date,
+                racing_number: c.driver_number.to_string(),
+                path: c.recording_url,
+            })
+            .collect())
+    }
+
+    /// Results of a session, from the `session_result` and `drivers` endpoints.
+    pub fn session_result(&self, session: &Session) -> Result<SessionResult, Box<dyn Error>> {
+        let kind = session
+            .session()
+            .ok_or_else(|| format!("No results for `{}`.", session.session_name))?;
+        let mut results: Vec<RawSessionResult> =
+            self.get("session_result", &[("session_key", session.session_key)])?;
+        results.sort_by_key(|r| r.position.unwrap_or(u32::MAX));
+        let drivers: HashMap<String, RawDriver> = self
+            .get::<RawDriver>("drivers", &[("session_key", session.session_key)])?
+            .into_iter()
+            .map(|d| (d.driver_number.to_string(), d))
+            .collect();
+        let driver = |number: u32| drivers.get(&number.to_string());
+        let text = |number: u32, f: fn(&RawDriver) -> &Option<String>| {
+            driver(number)
+                .and_then(|d| f(d).clone())
+                .unwrap_or_default()
+        };
+        let position = |r: &RawSessionResult| match (r.position, r.dsq) {
+            (_, true) => "DQ".to_string(),
+            (Some(position), _) => position.to_string(),
+            (None, _) => "NC".to_string(),
+        };
+        let finished = |r: &RawSessionResult| {
+            if r.dnf || r.dns || r.dsq {
+                CompletionStatusCode::Dnf
+            } else {
+                CompletionStatusCode::Ok
+            }
+        };
+        let time =
+            |r: &RawSessionResult| r.duration.as_f64().map(format_seconds).unwrap_or_default();
+        let laps_behind = |r: &RawSessionResult| {
+            r.gap_to_leader
+                .as_str()
+                .and_then(|g| g.trim_start_matches('+').split_whitespace().next())
+                .map(str::to_string)
+        };
+        let gap_to_leader = |r: &RawSessionResult| match &r.gap_to_leader {
+            Value::Number(n) if n.as_f64() != Some(0.0) => gap(&r.gap_to_leader),
+            _ => None,
+        };
+        let sheet = match kind {
+            Sessions::Race => ResultSheet::Race(
+                results
+                    .iter()
+                    .map(|r| RaceResult {
+                        team_colour_code: text(r.driver_number, |d| &d.team_colour),
+                        driver_tla: text(r.driver_number, |d| &d.name_acronym),
+                        team_name: text(r.driver_number, |d| &d.team_name),
+                        racing_number: r.driver_number.to_string(),
+                        position_number: position(r),
+                        driver_first_name: text(r.driver_number, |d| &d.first_name),
+                        driver_last_name: text(r.driver_number, |d| &d.last_name),
+                        driver_name_format: String::new(),
+                        gap_to_previous: None,
+                        gap_to_leader: gap_to_leader(r),
+                        completion_status_code: finished(r),
+                        race_time: time(r),
+                        driver_reference: String::new(),
+                        laps_behind_leader: laps_behind(r),
+                        race_points: r.points.map_or(0, whole_points),
+                        driver_image_url: String::new(),
+                    })
+                    .collect(),
+            ),
+            Sessions::Sprint => ResultSheet::Sprint(
+                results
+                    .iter()
+                    .map(|r| SprintResult {
+                        team_colour_code: text(r.driver_number, |d| &d.team_colour),
+                        driver_tla: text(r.driver_number, |d| &d.name_acronym),
+                        team_name: text(r.driver_number, |d| &d.team_name),
+                        racing_number: r.driver_number.to_string(),
+                        position_number: position(r),
+                        driver_first_name: text(r.driver_number, |d| &d.first_name),
+                        driver_last_name: text(r.driver_number, |d| &d.last_name),
+                        driver_name_format: String::new(),
+                        gap_to_previous: None,
+                        gap_to_leader: gap_to_leader(r),
+                        completion_status_code: finished(r),
+                        sprint_time: time(r),
+                        driver_reference: String::new(),
+                        laps_behind_leader: laps_behind(r),
+                        sprint_points: r.points.map_or(0, whole_points),
+                        driver_image_url: String::new(),
+                    })
+                    .collect(),
+            ),
+            Sessions::Qualifying => ResultSheet::Qualifying(
+                results
+                    .iter()
+                    .map(|r| {
+                        let segment = |i: usize| {
+                            r.duration
+                                .get(i)
+                                .and_then(Value::as_f64)
+                                .map(|s| Classifying {
+                                    classified_time: format_seconds(s),
+                                    completion_status_code: CompletionStatusCode::Ok,
+                                })
+                        };
+                        DriverQualifyingResult {
+                            q1: segment(0),
+                            q2: segment(1),
+                            q3: segment(2),
+                            team_colour_code: text(r.driver_number, |d| &d.team_colour),
+                            driver_tla: text(r.driver_number, |d| &d.name_acronym),
+                            team_name: text(r.driver_number, |d| &d.team_name),
+                            racing_number: r.driver_number.to_string(),
+                            position_number: position(r),
+                            driver_first_name: text(r.driver_number, |d| &d.first_name),
+                            driver_last_name: text(r.driver_number, |d| &d.last_name),
+                            driver_name_format: String::new(),
+                        }
+                    })
+                    .collect(),
+            ),
+            Sessions::FreePractice1 | Sessions::FreePractice2 | Sessions::FreePractice3 => {
+                let practice: Vec<PracticeResult> = results
+                    .iter()
+                    .map(|r| PracticeResult {
+                        team_colour_code: text(r.driver_number, |d| &d.team_colour),
+                        driver_tla: text(r.driver_number, |d| &d.name_acronym),
+                        team_name: text(r.driver_number, |d| &d.team_name),
+                        racing_number: r.driver_number.to_string(),
+                        position_number: position(r),
+                        driver_first_name: text(r.driver_number, |d| &d.first_name),
+                        driver_last_name: text(r.driver_number, |d| &d.last_name),
+                        driver_name_format: String::new(),
+                        gap_to_leader: gap_to_leader(r).unwrap_or_default(),
+                        classified_time: time(r),
+                    })
+                    .collect();
+                match kind {
+                    Sessions::FreePractice1 => ResultSheet::FreePractice1(practice),
+                    Sessions::FreePractice2 => ResultSheet::FreePractice2(practice),
+                    _ => ResultSheet::FreePractice3(practice),
+                }
+            }
+            Sessions::SprintShootout => {
+                return Err("Sprint shootout results have no place in the model yet.".into())
+            }
+        };
+        Ok(SessionResult {
+            state: status(session.date_end, self.clock.now()),
+            session_info: None,
+            results: sheet,
+        })
+    }
+}
+
+fn parse_key(key: &str) -> Result<u32, Box<dyn Error>> {
+    key.parse()
+        .map_err(|_| format!("`{key}` is not an OpenF1 key, like `1214`.").into())
+}
+
+impl OpenF1 {
+    fn session_key(&self, session: &Sessions, meeting_key: &str) -> Result<u32, Box<dyn Error>> {
+        Ok(self.session(parse_key(meeting_key)?, *session)?.session_key)
+    }
+
+    /// Racing number of a driver given by number or TLA.
+    fn driver_number(&self, session_key: u32, driver: &str) -> Result<u32, Box<dyn Error>> {
+        let drivers = self.drivers(session_key)?;
+        let driver = find_driver(&drivers, driver)
+            .ok_or_else(|| format!("No driver `{driver}` in session {session_key}."))?;
+        Ok(driver.racing_number.parse()?)
+    }
+}
+
+impl DataSource for OpenF1 {
+    fn name(&self) -> &str {
+        "openf1"
+    }
+
+    fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+        let year = parse_key(year)?;
+        let mut meetings = self.meetings(year)?;
+        meetings.sort_by_key(|m| m.date_start);
+        let sessions = self.sessions_of_year(year)?;
+        let now = self.clock.now();
+        let mut round = 0;
+        let mut events = Vec::new();
+        for meeting in meetings {
+            let end = sessions
+                .iter()
+                .filter(|s| s.meeting_key == meeting.meeting_key)
+                .map(|s| s.date_end)
+                .max()
+                .unwrap_or(meeting.date_start);
+            let round_text = if meeting.meeting_name.contains("Testing") {
+                "TESTING".to_string()
+            } else {
+                round += 1;
+                format!("ROUND {round}")
+            };
+            let (gmt_offset, offset) = parse_offset(&meeting.gmt_offset);
+            events.push(Event {
+                circuit_medium_image_url: String::new(),
+                meeting_country_name: meeting.country_name,
+                meeting_location: meeting.location,
+                meeting_official_name: meeting.meeting_official_name,
+                meeting_start_date: meeting.date_start + offset,
+                meeting_end_date: end + offset,
+                meeting_key: meeting.meeting_key.to_string(),
+                meeting_name: meeting.meeting_name,
+                gmt_offset,
+                status: status(end, now),
+                round_text,
+            });
+        }
+        Ok(events)
+    }
+
+    fn timetables(&self, meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        let mut sessions = self.sessions(parse_key(meeting_key)?)?;
+        sessions.sort_by_key(|s| s.date_start);
+        let now = self.clock.now();
+        Ok(sessions.iter().filter_map(|s| s.timetable(now)).collect())
+    }
+
+    fn results(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<SessionResult, Box<dyn Error>> {
+        self.session_result(&self.session(parse_key(meeting_key)?, *session)?)
+    }
+
+    fn standings(&self, year: &str) -> Result<Standings, Box<dyn Error>> {
+        let now = self.clock.now();
+        let mut results = Vec::new();
+        for session in self.sessions_of_year(parse_key(year)?)? {
+            let scoring = matches!(session.session(), Some(Sessions::Race | Sessions::Sprint));
+            if scoring && status(session.date_end, now) == Status::Completed {
+                results.push(self.session_result(&session)?);
+            }
+        }
+        Ok(compute_standings(&results))
+    }
+
+    fn drivers(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<Driver>, Box<dyn Error>> {
+        OpenF1::drivers(self, self.session_key(session, meeting_key)?)
+    }
+
+    fn car_data(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<CarDataSample>, Box<dyn Error>> {
+        let session_key = self.session_key(session, meeting_key)?;
+        OpenF1::car_data(self, session_key, self.driver_number(session_key, driver)?)
+    }
+
+    fn positions(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<PositionSample>, Box<dyn Error>> {
+        let session_key = self.session_key(session, meeting_key)?;
+        self.location(session_key, self.driver_number(session_key, driver)?)
+    }
+
+    fn race_control(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<RaceControlMessage>, Box<dyn Error>> {
+        OpenF1::race_control(self, self.session_key(session, meeting_key)?)
+    }
+
+    fn weather(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<WeatherSample>, Box<dyn Error>> {
+        OpenF1::weather(self, self.session_key(session, meeting_key)?)
+    }
+
+    fn strategy(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<RaceStrategy, Box<dyn Error>> {
+        OpenF1::strategy(self, self.session_key(session, meeting_key)?)
+    }
+}
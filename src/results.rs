@@ -1,12 +1,22 @@
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 
-use crate::weekend_common::{self, custom_date_format, weekend_session};
-use crate::{utils::get_from_api, weekend_common::Sessions, weekend_common::Status};
-use serde::{Deserialize, Deserializer, Serialize};
+use crate::weekend_common::custom_date_format;
+use crate::{
+    schedule::FORMULA1_URL, utils::get_from_api, weekend_common::Sessions, weekend_common::Status,
+};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 
 pub fn get_results(session: &Sessions, meeting_key: &str) -> Result<SessionResult, Box<dyn Error>> {
+    results_from(FORMULA1_URL, session, meeting_key)
+}
+
+pub(crate) fn results_from(
+    base_url: &str,
+    session: &Sessions,
+    meeting_key: &str,
+) -> Result<SessionResult, Box<dyn Error>> {
     let (session_key, session_extra, json_key) = match session {
         Sessions::Race => ("race", None, "raceResultsRace"),
         Sessions::Sprint => ("sprint", None, "raceResultsSprint"),
@@ -15,17 +25,15 @@ pub fn get_results(session: &Sessions, meeting_key: &str) -> Result<SessionResul
         Sessions::FreePractice2 => ("practice", Some("2"), "raceResultsPractice2"),
         Sessions::FreePractice3 => ("practice", Some("3"), "raceResultsPractice3"),
         Sessions::SprintShootout => ("sprint-shootout", None, "raceResultsSprintShootout"),
-        _ => panic!(),
     };
     let session_number = if let Some(n) = session_extra {
         format!("&session={n}")
     } else {
         String::new()
     };
-    let url =
-        format!("https://api.formula1.com/v1/fom-results/{session_key}?meeting={meeting_key}{session_number}");
+    let url = format!("{base_url}fom-results/{session_key}?meeting={meeting_key}{session_number}");
     let json = get_from_api(&url)?;
-    let mut v: Value = serde_json::from_str(&*json)?;
+    let mut v: Value = serde_json::from_str(&json)?;
     let e: Value = v
         .get_mut(json_key)
         .ok_or(format!("Key `{json_key}` not in received json."))?
@@ -65,6 +73,138 @@ impl ResultSheet {
             ResultSheet::FreePractice3(_) => Sessions::FreePractice3,
         }
     }
+
+    /// Every line flattened to the same shape, see [`ResultRow`].
+    pub fn rows(&self) -> Vec<ResultRow> {
+        fn q(q: &Option<Classifying>) -> Option<String> {
+            q.as_ref()
+                .map(|q| q.classified_time.clone())
+                .filter(|t| !t.is_empty())
+        }
+        let finished = |code: &CompletionStatusCode| Some(matches!(code, CompletionStatusCode::Ok));
+        let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+        let row = |position: &str,
+                   number: &str,
+                   tla: &str,
+                   first: &str,
+                   last: &str,
+                   team: &str,
+                   colour: &str| ResultRow {
+            position: position.parse().ok(),
+            racing_number: number.to_string(),
+            driver_tla: tla.to_string(),
+            first_name: first.to_string(),
+            last_name: last.to_string(),
+            team_name: team.to_string(),
+            team_colour: colour.to_string(),
+            time: None,
+            gap_to_leader: None,
+            laps_behind_leader: None,
+            finished: None,
+            points: None,
+            q1: None,
+            q2: None,
+            q3: None,
+        };
+        match self {
+            ResultSheet::Race(results) => results
+                .iter()
+                .map(|r| ResultRow {
+                    time: non_empty(&r.race_time),
+                    gap_to_leader: r.gap_to_leader.clone(),
+                    laps_behind_leader: r.laps_behind_leader.as_ref().and_then(|l| l.parse().ok()),
+                    finished: finished(&r.completion_status_code),
+                    points: Some(r.race_points),
+                    ..row(
+                        &r.position_number,
+                        &r.racing_number,
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        &r.team_colour_code,
+                    )
+                })
+                .collect(),
+            ResultSheet::Sprint(results) => results
+                .iter()
+                .map(|r| ResultRow {
+                    time: non_empty(&r.sprint_time),
+                    gap_to_leader: r.gap_to_leader.clone(),
+                    laps_behind_leader: r.laps_behind_leader.as_ref().and_then(|l| l.parse().ok()),
+                    finished: finished(&r.completion_status_code),
+                    points: Some(r.sprint_points),
+                    ..row(
+                        &r.position_number,
+                        &r.racing_number,
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        &r.team_colour_code,
+                    )
+                })
+                .collect(),
+            ResultSheet::Qualifying(results) => results
+                .iter()
+                .map(|r| ResultRow {
+                    q1: q(&r.q1),
+                    q2: q(&r.q2),
+                    q3: q(&r.q3),
+                    ..row(
+                        &r.position_number,
+                        &r.racing_number,
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        &r.team_colour_code,
+                    )
+                })
+                .collect(),
+            ResultSheet::FreePractice1(results)
+            | ResultSheet::FreePractice2(results)
+            | ResultSheet::FreePractice3(results) => results
+                .iter()
+                .map(|r| ResultRow {
+                    time: non_empty(&r.classified_time),
+                    gap_to_leader: non_empty(&r.gap_to_leader),
+                    ..row(
+                        &r.position_number,
+                        &r.racing_number,
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        &r.team_colour_code,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A line of any results sheet, the fields that don't apply to the session are `None`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResultRow {
+    /// `None` when not classified.
+    pub position: Option<u32>,
+    pub racing_number: String,
+    pub driver_tla: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub team_name: String,
+    pub team_colour: String,
+    pub time: Option<String>,
+    pub gap_to_leader: Option<String>,
+    pub laps_behind_leader: Option<u32>,
+    /// Race and sprint only.
+    pub finished: Option<bool>,
+    /// Race and sprint only.
+    pub points: Option<i32>,
+    pub q1: Option<String>,
+    pub q2: Option<String>,
+    pub q3: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -80,9 +220,9 @@ pub enum CompletionStatusCode {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Classifying {
     #[serde(rename = "classifiedTime")]
-    classified_time: String,
+    pub classified_time: String,
     #[serde(rename = "completionStatusCode")]
-    completion_status_code: CompletionStatusCode,
+    pub completion_status_code: CompletionStatusCode,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -237,6 +377,7 @@ impl SessionResult {
 }
 
 // #[macro_use]
+#[allow(unused_macros)]
 macro_rules! from_get_value {
     ($value:expr, $field:literal, $key_not_present_err:expr) => {
         serde_json::from_value($value.get_mut($field).ok_or($key_not_present_err)?.take())
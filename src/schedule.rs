@@ -2,19 +2,46 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 
-use crate::weekend_common::{custom_date_format, weekend_session};
+use crate::weekend_common::{custom_date_format, to_utc, weekend_session};
 
 use serde_json::Value;
 
 use crate::{
+    clock::Clock,
+    source::{DataSource, Formula1},
+    upcoming::{self, WeekendPhase},
     utils::get_from_api,
-    weekend_common::{Sessions, Status},
+    weekend_common::{Sessions, Status, WeekendFormat},
 };
 
+/// Where the free functions of this module and [`crate::results`] ask, see
+/// [`crate::source::Formula1::with_base_url`] for another one.
+pub const FORMULA1_URL: &str = "https://api.formula1.com/v1/";
+
+/// The weekend formula1.com has on now. It goes by the real time whatever clock the caller
+/// uses, [`get_current_weekend_with`] takes the time from a clock.
 pub fn get_current_weekend() -> Result<CurrentWeekend, Box<dyn Error>> {
-    let url = format!("https://api.formula1.com/v1/event-tracker");
+    current_weekend_from(FORMULA1_URL)
+}
+
+/// The weekend on at the time of `clock`, or the next one, worked out from the calendar with
+/// [`crate::upcoming::weekend_at`].
+pub fn get_current_weekend_with(clock: &dyn Clock) -> Result<CurrentWeekend, Box<dyn Error>> {
+    current_weekend_at(&Formula1::new(), clock.now())
+}
+
+pub(crate) fn current_weekend_at(
+    source: &dyn DataSource,
+    now: DateTime<Utc>,
+) -> Result<CurrentWeekend, Box<dyn Error>> {
+    upcoming::weekend_at_from(source, now)?
+        .ok_or_else(|| format!("No weekend on or after {now}.").into())
+}
+
+pub(crate) fn current_weekend_from(base_url: &str) -> Result<CurrentWeekend, Box<dyn Error>> {
+    let url = format!("{base_url}event-tracker");
     let json = get_from_api(&url)?;
-    let mut v: Value = serde_json::from_str(&*json)?;
+    let mut v: Value = serde_json::from_str(&json)?;
     let e: Value = v
         .get_mut("seasonContext")
         .ok_or("Key `seasonContext` not in received json.")?
@@ -24,12 +51,16 @@ pub fn get_current_weekend() -> Result<CurrentWeekend, Box<dyn Error>> {
 }
 
 pub fn get_timetables(meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
-    let url = format!(
-        "https://api.formula1.com/v1/fom-results/timetables?meeting={}",
-        meeting_key
-    );
+    timetables_from(FORMULA1_URL, meeting_key)
+}
+
+pub(crate) fn timetables_from(
+    base_url: &str,
+    meeting_key: &str,
+) -> Result<Vec<Timetable>, Box<dyn Error>> {
+    let url = format!("{base_url}fom-results/timetables?meeting={meeting_key}");
     let json = get_from_api(&url)?;
-    let mut v: Value = serde_json::from_str(&*json)?;
+    let mut v: Value = serde_json::from_str(&json)?;
     let e: Value = v
         .get_mut("timetables")
         .ok_or("Key `timetables` not in received json.")?
@@ -38,9 +69,13 @@ pub fn get_timetables(meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error
 }
 
 pub fn get_calendar(year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
-    let url = format!("https://api.formula1.com/v1/editorial-eventlisting/events?season={year}");
+    calendar_from(FORMULA1_URL, year)
+}
+
+pub(crate) fn calendar_from(base_url: &str, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+    let url = format!("{base_url}editorial-eventlisting/events?season={year}");
     let json = get_from_api(&url)?;
-    let mut v: Value = serde_json::from_str(&*json)?;
+    let mut v: Value = serde_json::from_str(&json)?;
     let e: Value = v
         .get_mut("events")
         .ok_or("Key `events` not in received json.")?
@@ -57,54 +92,102 @@ pub enum EventType {
     Testing,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Event {
     #[serde(rename = "circuitMediumImage")]
-    circuit_medium_image_url: String,
+    pub circuit_medium_image_url: String,
     #[serde(rename = "meetingCountryName")]
-    meeting_country_name: String,
+    pub meeting_country_name: String,
     #[serde(rename = "meetingLocation")]
-    meeting_location: String,
+    pub meeting_location: String,
     #[serde(rename = "meetingOfficialName")]
-    meeting_official_name: String,
+    pub meeting_official_name: String,
     #[serde(with = "custom_date_format")]
     #[serde(rename = "meetingStartDate")]
-    meeting_start_date: DateTime<Utc>,
+    pub meeting_start_date: DateTime<Utc>,
     #[serde(with = "custom_date_format")]
     #[serde(rename = "meetingEndDate")]
-    meeting_end_date: DateTime<Utc>,
+    pub meeting_end_date: DateTime<Utc>,
     #[serde(rename = "meetingKey")]
-    meeting_key: String,
+    pub meeting_key: String,
     #[serde(rename = "meetingName")]
-    meeting_name: String,
+    pub meeting_name: String,
     #[serde(rename = "gmtOffset")]
-    gmt_offset: String,
-    status: Status,
+    pub gmt_offset: String,
+    pub status: Status,
     #[serde(rename = "roundText")]
-    rount_text: String,
+    pub round_text: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Event {
+    /// Number of the round, `None` for testing.
+    pub fn round(&self) -> Option<u32> {
+        self.round_text
+            .split_whitespace()
+            .last()
+            .and_then(|n| n.parse().ok())
+    }
+
+    /// From the formula1.com timetable of the event.
+    pub fn weekend_format(&self) -> Result<Option<WeekendFormat>, Box<dyn Error>> {
+        Ok(WeekendFormat::detect(&get_timetables(&self.meeting_key)?))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Timetable {
-    state: Status,
+    pub state: Status,
     #[serde(with = "weekend_session")]
-    session: Sessions,
-    description: String,
+    pub session: Sessions,
+    pub description: String,
     #[serde(with = "custom_date_format")]
     #[serde(rename = "startTime")]
-    start_date: DateTime<Utc>,
+    pub start_date: DateTime<Utc>,
     #[serde(with = "custom_date_format")]
     #[serde(rename = "endTime")]
-    end_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
     #[serde(rename = "gmtOffset")]
-    gmt_offset: String,
+    pub gmt_offset: String,
+}
+
+impl Timetable {
+    /// `start_date` is local time at the track, this is the actual start.
+    pub fn start_utc(&self) -> DateTime<Utc> {
+        to_utc(self.start_date, &self.gmt_offset)
+    }
+
+    pub fn end_utc(&self) -> DateTime<Utc> {
+        to_utc(self.end_date, &self.gmt_offset)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CurrentWeekend {
     #[serde(rename = "seasonYear")]
-    season_year: String,
+    pub season_year: String,
     #[serde(rename = "currentOrNextMeetingKey")]
-    current_or_next_meeting_key: String,
-    timetables: Vec<Timetable>,
+    pub current_or_next_meeting_key: String,
+    pub timetables: Vec<Timetable>,
+}
+
+impl CurrentWeekend {
+    pub fn next_session(&self, now: DateTime<Utc>) -> Option<&Timetable> {
+        upcoming::next_in(&self.timetables, now)
+    }
+
+    pub fn live_session(&self, now: DateTime<Utc>) -> Option<&Timetable> {
+        upcoming::live_in(&self.timetables, now)
+    }
+
+    pub fn previous_session(&self, now: DateTime<Utc>) -> Option<&Timetable> {
+        upcoming::previous_in(&self.timetables, now)
+    }
+
+    pub fn phase(&self, now: DateTime<Utc>) -> WeekendPhase {
+        upcoming::phase_in(&self.timetables, now)
+    }
+
+    pub fn format(&self) -> Option<WeekendFormat> {
+        WeekendFormat::detect(&self.timetables)
+    }
 }
@@ -0,0 +1,257 @@
+//! REST api over the library, for services that are not written in Rust. Everything is served
+//! under `/v1` in our own snake_case schema, so upstream changes to the formula1.com api don't
+//! leak through to clients.
+//!
+//! | Route                                      | Body                          |
+//! |--------------------------------------------|-------------------------------|
+//! | `GET /v1/calendar/{year}`                  | `[EventV1]`                   |
+//! | `GET /v1/weekend/current`                  | `WeekendV1`                   |
+//! | `GET /v1/events/{meeting_key}/timetable`   | `[SessionV1]`                 |
+//! | `GET /v1/events/{meeting_key}/results/{session}` | `SessionResultV1`       |
+//! | `GET /v1/standings/{year}`                 | [`Standings`]                 |
+//!
+//! Sessions are `race`, `sprint`, `qualifying`, `sprint_shootout`, `practice_1`, `practice_2`
+//! and `practice_3`, there are no results of `sprint_shootout` yet. Errors are
+//! `{"error": "..."}` with a 4xx or 5xx status.
+
+use std::{error::Error, panic};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tiny_http::{Header, Response, Server};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    results::{ResultRow, SessionResult},
+    schedule::{current_weekend_at, Event, Timetable},
+    source::{DataSource, Formula1},
+    standings::Standings,
+    weekend_common::{to_utc, Sessions, Status},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusV1 {
+    Completed,
+    Upcoming,
+    Unknown,
+}
+
+impl From<Status> for StatusV1 {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Completed => StatusV1::Completed,
+            Status::Upcoming => StatusV1::Upcoming,
+            Status::Na => StatusV1::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionV1Kind {
+    Race,
+    Sprint,
+    Qualifying,
+    SprintShootout,
+    #[serde(rename = "practice_1")]
+    Practice1,
+    #[serde(rename = "practice_2")]
+    Practice2,
+    #[serde(rename = "practice_3")]
+    Practice3,
+}
+
+impl From<Sessions> for SessionV1Kind {
+    fn from(session: Sessions) -> Self {
+        match session {
+            Sessions::Race => SessionV1Kind::Race,
+            Sessions::Sprint => SessionV1Kind::Sprint,
+            Sessions::Qualifying => SessionV1Kind::Qualifying,
+            Sessions::SprintShootout => SessionV1Kind::SprintShootout,
+            Sessions::FreePractice1 => SessionV1Kind::Practice1,
+            Sessions::FreePractice2 => SessionV1Kind::Practice2,
+            Sessions::FreePractice3 => SessionV1Kind::Practice3,
+        }
+    }
+}
+
+impl From<SessionV1Kind> for Sessions {
+    fn from(session: SessionV1Kind) -> Self {
+        match session {
+            SessionV1Kind::Race => Sessions::Race,
+            SessionV1Kind::Sprint => Sessions::Sprint,
+            SessionV1Kind::Qualifying => Sessions::Qualifying,
+            SessionV1Kind::SprintShootout => Sessions::SprintShootout,
+            SessionV1Kind::Practice1 => Sessions::FreePractice1,
+            SessionV1Kind::Practice2 => Sessions::FreePractice2,
+            SessionV1Kind::Practice3 => Sessions::FreePractice3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EventV1 {
+    pub meeting_key: String,
+    /// `None` for testing.
+    pub round: Option<u32>,
+    pub name: String,
+    pub official_name: String,
+    pub country: String,
+    pub location: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub status: StatusV1,
+}
+
+impl From<&Event> for EventV1 {
+    fn from(event: &Event) -> Self {
+        EventV1 {
+            meeting_key: event.meeting_key.clone(),
+            round: event.round(),
+            name: event.meeting_name.clone(),
+            official_name: event.meeting_official_name.clone(),
+            country: event.meeting_country_name.clone(),
+            location: event.meeting_location.clone(),
+            start_date: event.meeting_start_date.date_naive(),
+            end_date: event.meeting_end_date.date_naive(),
+            status: event.status.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionV1 {
+    pub session: SessionV1Kind,
+    pub name: String,
+    pub status: StatusV1,
+    pub start_utc: DateTime<Utc>,
+    pub end_utc: DateTime<Utc>,
+    /// Offset of the local time at the track, like `+02:00`.
+    pub gmt_offset: String,
+}
+
+impl From<&Timetable> for SessionV1 {
+    fn from(timetable: &Timetable) -> Self {
+        SessionV1 {
+            session: timetable.session.into(),
+            name: timetable.description.clone(),
+            status: timetable.state.into(),
+            start_utc: timetable.start_utc(),
+            end_utc: timetable.end_utc(),
+            gmt_offset: timetable.gmt_offset.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeekendV1 {
+    pub season: String,
+    pub meeting_key: String,
+    pub sessions: Vec<SessionV1>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionResultV1 {
+    pub session: SessionV1Kind,
+    pub status: StatusV1,
+    pub start_utc: Option<DateTime<Utc>>,
+    pub end_utc: Option<DateTime<Utc>>,
+    /// Fields that don't apply to the session are `null`.
+    pub results: Vec<ResultRow>,
+}
+
+impl From<&SessionResult> for SessionResultV1 {
+    fn from(result: &SessionResult) -> Self {
+        let info = result.session_info.as_ref();
+        SessionResultV1 {
+            session: result.session().into(),
+            status: result.state.into(),
+            start_utc: info.map(|i| to_utc(i.start_date, &i.gmt_offset)),
+            end_utc: info.map(|i| to_utc(i.end_date, &i.gmt_offset)),
+            results: result.results.rows(),
+        }
+    }
+}
+
+/// Answers a request from `f1`, returns the status code and the json body. The current weekend
+/// is the one on at the time of `clock`.
+pub fn handle(f1: &Formula1, clock: &dyn Clock, method: &str, url: &str) -> (u16, String) {
+    if method != "GET" {
+        return error(405, "Only GET is supported.");
+    }
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let body: Result<String, Box<dyn Error>> = match segments.as_slice() {
+        ["v1", "calendar", year] => f1.calendar(year).and_then(|events| {
+            let events: Vec<EventV1> = events.iter().map(EventV1::from).collect();
+            Ok(serde_json::to_string(&events)?)
+        }),
+        ["v1", "weekend", "current"] => current_weekend_at(f1, clock.now()).and_then(|weekend| {
+            Ok(serde_json::to_string(&WeekendV1 {
+                season: weekend.season_year.clone(),
+                meeting_key: weekend.current_or_next_meeting_key.clone(),
+                sessions: weekend.timetables.iter().map(SessionV1::from).collect(),
+            })?)
+        }),
+        ["v1", "events", meeting_key, "timetable"] => {
+            f1.timetables(meeting_key).and_then(|timetables| {
+                let sessions: Vec<SessionV1> = timetables.iter().map(SessionV1::from).collect();
+                Ok(serde_json::to_string(&sessions)?)
+            })
+        }
+        ["v1", "events", meeting_key, "results", session] => {
+            let Ok(session) = serde_json::from_value::<SessionV1Kind>(json!(session)) else {
+                return error(400, &format!("Unknown session `{session}`."));
+            };
+            if session == SessionV1Kind::SprintShootout {
+                return error(400, "Sprint shootout results aren't supported yet.");
+            }
+            f1.results(&session.into(), meeting_key)
+                .and_then(|result| Ok(serde_json::to_string(&SessionResultV1::from(&result))?))
+        }
+        ["v1", "standings", year] => f1
+            .standings(year)
+            .and_then(|standings: Standings| Ok(serde_json::to_string(&standings)?)),
+        _ => return error(404, &format!("No route for `{path}`.")),
+    };
+    match body {
+        Ok(body) => (200, body),
+        Err(e) => error(502, &e.to_string()),
+    }
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": message }).to_string())
+}
+
+/// Serves the api of `f1` on `addr` (like `127.0.0.1:8080`) until the process ends. Responses
+/// of the formula1.com api are cached when [`crate::utils::enable_cache`] was called. Responses
+/// that couldn't be sent go to `on_error`.
+pub fn serve(
+    addr: &str,
+    f1: &Formula1,
+    mut on_error: impl FnMut(&dyn Error),
+) -> Result<(), Box<dyn Error>> {
+    let server = Server::http(addr).map_err(|e| e.to_string())?;
+    let content_type: Header = "Content-Type: application/json"
+        .parse()
+        .map_err(|_| "Invalid header.")?;
+    for request in server.incoming_requests() {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        // A bad upstream payload should cost one request, not the whole server.
+        let (status, body) = panic::catch_unwind(|| handle(f1, &SystemClock, &method, &url))
+            .unwrap_or_else(|_| error(500, "Internal error."));
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        // A client that went away costs its own response only.
+        if let Err(e) = request.respond(response) {
+            let e: Box<dyn Error> = format!("{method} {url}: {e}").into();
+            on_error(e.as_ref());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    analysis::grid::GridSlot,
+    results::{results_from, SessionResult},
+    schedule::{
+        calendar_from, current_weekend_from, timetables_from, CurrentWeekend, Event, Timetable,
+        FORMULA1_URL,
+    },
+    standings::{standings_from, Standings},
+    telemetry::{
+        car_data::CarDataSample, driver_list::Driver, position::PositionSample,
+        race_control::RaceControlMessage, stints::RaceStrategy, weather::WeatherSample,
+    },
+    weekend_common::Sessions,
+};
+
+/// A provider of schedules, results and telemetry, mapped onto the crate's model types. Meeting
+/// keys are whatever the provider uses in [`Event::meeting_key`], drivers are given by racing
+/// number or TLA. Telemetry queries fail unless the provider has them.
+pub trait DataSource {
+    /// Used in error messages.
+    fn name(&self) -> &str;
+
+    fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>>;
+
+    fn timetables(&self, meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>>;
+
+    fn results(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<SessionResult, Box<dyn Error>>;
+
+    fn standings(&self, year: &str) -> Result<Standings, Box<dyn Error>>;
+
+    fn drivers(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<Driver>, Box<dyn Error>> {
+        let _ = (session, meeting_key);
+        Err(unsupported(self.name(), "drivers"))
+    }
+
+    fn car_data(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<CarDataSample>, Box<dyn Error>> {
+        let _ = (session, meeting_key, driver);
+        Err(unsupported(self.name(), "car data"))
+    }
+
+    fn positions(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<PositionSample>, Box<dyn Error>> {
+        let _ = (session, meeting_key, driver);
+        Err(unsupported(self.name(), "positions"))
+    }
+
+    fn race_control(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<RaceControlMessage>, Box<dyn Error>> {
+        let _ = (session, meeting_key);
+        Err(unsupported(self.name(), "race control messages"))
+    }
+
+    fn weather(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<WeatherSample>, Box<dyn Error>> {
+        let _ = (session, meeting_key);
+        Err(unsupported(self.name(), "weather"))
+    }
+
+    fn strategy(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<RaceStrategy, Box<dyn Error>> {
+        let _ = (session, meeting_key);
+        Err(unsupported(self.name(), "tyre strategies"))
+    }
+
+    /// The grid of a race or sprint as it lined up, penalties applied.
+    fn starting_grid(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<GridSlot>, Box<dyn Error>> {
+        let _ = (session, meeting_key);
+        Err(unsupported(self.name(), "starting grids"))
+    }
+}
+
+/// The maps of [`Composite`] are only ever inserted into, they're fine after a panic.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn unsupported(source: &str, query: &str) -> Box<dyn Error> {
+    format!("{source} has no {query}.").into()
+}
+
+/// The driver given by racing number or TLA.
+pub fn find_driver<'a>(drivers: &'a [Driver], driver: &str) -> Option<&'a Driver> {
+    drivers
+        .iter()
+        .find(|d| d.racing_number == driver || d.tla.eq_ignore_ascii_case(driver))
+}
+
+/// The formula1.com api, what the free functions of [`crate::schedule`] and [`crate::results`]
+/// use. Only covers recent seasons.
+#[derive(Debug, Clone)]
+pub struct Formula1 {
+    base_url: String,
+}
+
+impl Default for Formula1 {
+    fn default() -> Self {
+        Formula1::with_base_url(FORMULA1_URL)
+    }
+}
+
+impl Formula1 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// For a proxy or a recording of the api, `base_url` is the part before `event-tracker`.
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Formula1 { base_url }
+    }
+
+    /// See [`crate::schedule::get_current_weekend`].
+    pub fn current_weekend(&self) -> Result<CurrentWeekend, Box<dyn Error>> {
+        current_weekend_from(&self.base_url)
+    }
+}
+
+impl DataSource for Formula1 {
+    fn name(&self) -> &str {
+        "formula1.com"
+    }
+
+    fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+        calendar_from(&self.base_url, year)
+    }
+
+    fn timetables(&self, meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        timetables_from(&self.base_url, meeting_key)
+    }
+
+    fn results(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<SessionResult, Box<dyn Error>> {
+        results_from(&self.base_url, session, meeting_key)
+    }
+
+    fn standings(&self, year: &str) -> Result<Standings, Box<dyn Error>> {
+        standings_from(self, year)
+    }
+}
+
+/// What identifies an event across providers, their meeting keys differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventIdentity {
+    pub round: Option<u32>,
+    pub start: DateTime<Utc>,
+}
+
+impl EventIdentity {
+    pub fn of(event: &Event) -> Self {
+        EventIdentity {
+            round: event.round(),
+            start: event.meeting_start_date,
+        }
+    }
+
+    /// Same round, testing events (no round) by start within a few days, providers don't agree
+    /// on the first day of a meeting.
+    pub fn matches(&self, other: &EventIdentity) -> bool {
+        match (self.round, other.round) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => (self.start - other.start).num_days().abs() <= 3,
+            _ => false,
+        }
+    }
+}
+
+/// Tries its providers in order until one answers. Meeting keys of the events returned by
+/// [`DataSource::calendar`] are translated for every provider, other keys are passed as they
+/// are. Providers are told apart by [`DataSource::name`].
+pub struct Composite {
+    sources: Vec<Box<dyn DataSource>>,
+    /// Events seen in calendars, by meeting key.
+    events: Mutex<HashMap<String, KnownEvent>>,
+    /// Translated meeting keys by provider name and meeting key.
+    keys: Mutex<HashMap<(String, String), String>>,
+}
+
+#[derive(Debug, Clone)]
+struct KnownEvent {
+    /// Name of the provider whose calendar had the event.
+    source: String,
+    year: String,
+    identity: EventIdentity,
+}
+
+impl Composite {
+    pub fn new(sources: Vec<Box<dyn DataSource>>) -> Self {
+        Composite {
+            sources,
+            events: Mutex::new(HashMap::new()),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The meeting key `source` uses for the event of `meeting_key`. A provider's calendar is
+    /// downloaded once per season, the keys of every known event of it are kept.
+    pub fn meeting_key(
+        &self,
+        source: &dyn DataSource,
+        meeting_key: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let known = lock(&self.events).get(meeting_key).cloned();
+        let Some(known) = known else {
+            return Ok(meeting_key.to_string());
+        };
+        if known.source == source.name() {
+            return Ok(meeting_key.to_string());
+        }
+        let cache_key = (source.name().to_string(), meeting_key.to_string());
+        if let Some(key) = lock(&self.keys).get(&cache_key) {
+            return Ok(key.clone());
+        }
+        let calendar = source.calendar(&known.year)?;
+        let season: Vec<(String, EventIdentity)> = lock(&self.events)
+            .iter()
+            .filter(|(_, e)| e.year == known.year && e.source != source.name())
+            .map(|(key, e)| (key.clone(), e.identity))
+            .collect();
+        let mut keys = lock(&self.keys);
+        for (key, identity) in season {
+            if let Some(event) = calendar
+                .iter()
+                .find(|e| identity.matches(&EventIdentity::of(e)))
+            {
+                keys.insert((source.name().to_string(), key), event.meeting_key.clone());
+            }
+        }
+        keys.get(&cache_key).cloned().ok_or_else(|| {
+            format!("{} has no event matching `{meeting_key}`.", source.name()).into()
+        })
+    }
+
+    fn first<T>(
+        &self,
+        query: impl Fn(&dyn DataSource) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match query(source.as_ref()) {
+                Ok(value) => return Ok(value),
+                Err(e) => errors.push(format!("{}: {e}", source.name())),
+            }
+        }
+        if errors.is_empty() {
+            return Err("No data sources.".into());
+        }
+        Err(errors.join("; ").into())
+    }
+
+    fn for_meeting<T>(
+        &self,
+        meeting_key: &str,
+        query: impl Fn(&dyn DataSource, &str) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        self.first(|source| query(source, &self.meeting_key(source, meeting_key)?))
+    }
+}
+
+impl DataSource for Composite {
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn calendar(&self, year: &str) -> Result<Vec<Event>, Box<dyn Error>> {
+        let (origin, events) =
+            self.first(|source| Ok((source.name().to_string(), source.calendar(year)?)))?;
+        let mut known = lock(&self.events);
+        for event in &events {
+            known.insert(
+                event.meeting_key.clone(),
+                KnownEvent {
+                    source: origin.clone(),
+                    year: year.to_string(),
+                    identity: EventIdentity::of(event),
+                },
+            );
+        }
+        Ok(events)
+    }
+
+    fn timetables(&self, meeting_key: &str) -> Result<Vec<Timetable>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.timetables(key))
+    }
+
+    fn results(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<SessionResult, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.results(session, key))
+    }
+
+    fn standings(&self, year: &str) -> Result<Standings, Box<dyn Error>> {
+        self.first(|source| source.standings(year))
+    }
+
+    fn drivers(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<Driver>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.drivers(session, key))
+    }
+
+    fn car_data(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<CarDataSample>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| {
+            source.car_data(session, key, driver)
+        })
+    }
+
+    fn positions(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+        driver: &str,
+    ) -> Result<Vec<PositionSample>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| {
+            source.positions(session, key, driver)
+        })
+    }
+
+    fn race_control(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<RaceControlMessage>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.race_control(session, key))
+    }
+
+    fn weather(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<WeatherSample>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.weather(session, key))
+    }
+
+    fn strategy(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<RaceStrategy, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| source.strategy(session, key))
+    }
+
+    fn starting_grid(
+        &self,
+        session: &Sessions,
+        meeting_key: &str,
+    ) -> Result<Vec<GridSlot>, Box<dyn Error>> {
+        self.for_meeting(meeting_key, |source, key| {
+            source.starting_grid(session, key)
+        })
+    }
+}
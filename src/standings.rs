@@ -0,0 +1,145 @@
+use std::{collections::HashMap, error::Error};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    results::{ResultSheet, SessionResult},
+    source::{DataSource, Formula1},
+    weekend_common::{Sessions, Status, WeekendFormat},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DriverStanding {
+    pub position: u32,
+    pub driver_tla: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub team_name: String,
+    pub points: i32,
+    pub wins: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConstructorStanding {
+    pub position: u32,
+    pub team_name: String,
+    pub points: i32,
+    pub wins: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Standings {
+    pub drivers: Vec<DriverStanding>,
+    pub constructors: Vec<ConstructorStanding>,
+}
+
+/// Championship standings from race and sprint results, other sessions are ignored. Only race
+/// wins count as wins, ties are broken on wins and then on name.
+pub fn compute_standings(results: &[SessionResult]) -> Standings {
+    let mut drivers: HashMap<String, DriverStanding> = HashMap::new();
+    let mut constructors: HashMap<String, ConstructorStanding> = HashMap::new();
+    let mut score = |tla: &str, first: &str, last: &str, team: &str, points: i32, win: bool| {
+        let driver = drivers
+            .entry(tla.to_string())
+            .or_insert_with(|| DriverStanding {
+                position: 0,
+                driver_tla: tla.to_string(),
+                first_name: first.to_string(),
+                last_name: last.to_string(),
+                team_name: String::new(),
+                points: 0,
+                wins: 0,
+            });
+        // Results come in calendar order, so this ends up as the latest team.
+        driver.team_name = team.to_string();
+        driver.points += points;
+        driver.wins += win as u32;
+        let constructor =
+            constructors
+                .entry(team.to_string())
+                .or_insert_with(|| ConstructorStanding {
+                    position: 0,
+                    team_name: team.to_string(),
+                    points: 0,
+                    wins: 0,
+                });
+        constructor.points += points;
+        constructor.wins += win as u32;
+    };
+    for result in results {
+        match &result.results {
+            ResultSheet::Race(sheet) => {
+                for r in sheet {
+                    let win = r.position_number == "1";
+                    score(
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        r.race_points,
+                        win,
+                    );
+                }
+            }
+            ResultSheet::Sprint(sheet) => {
+                for r in sheet {
+                    score(
+                        &r.driver_tla,
+                        &r.driver_first_name,
+                        &r.driver_last_name,
+                        &r.team_name,
+                        r.sprint_points,
+                        false,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut drivers: Vec<DriverStanding> = drivers.into_values().collect();
+    drivers.sort_by(|a, b| {
+        (b.points, b.wins)
+            .cmp(&(a.points, a.wins))
+            .then_with(|| a.last_name.cmp(&b.last_name))
+    });
+    for (i, driver) in drivers.iter_mut().enumerate() {
+        driver.position = i as u32 + 1;
+    }
+    let mut constructors: Vec<ConstructorStanding> = constructors.into_values().collect();
+    constructors.sort_by(|a, b| {
+        (b.points, b.wins)
+            .cmp(&(a.points, a.wins))
+            .then_with(|| a.team_name.cmp(&b.team_name))
+    });
+    for (i, constructor) in constructors.iter_mut().enumerate() {
+        constructor.position = i as u32 + 1;
+    }
+    Standings {
+        drivers,
+        constructors,
+    }
+}
+
+/// Standings after every completed event of `year`. Sprint results are only asked for
+/// weekends with a sprint, any failing lookup fails the standings.
+pub fn get_standings(year: &str) -> Result<Standings, Box<dyn Error>> {
+    Formula1::new().standings(year)
+}
+
+/// [`get_standings`] from the calendar, timetables and results of `source`.
+pub fn standings_from(source: &dyn DataSource, year: &str) -> Result<Standings, Box<dyn Error>> {
+    let mut results = Vec::new();
+    for event in source.calendar(year)? {
+        // Testing has no round.
+        if event.status != Status::Completed || event.round().is_none() {
+            continue;
+        }
+        let format = WeekendFormat::detect(&source.timetables(&event.meeting_key)?);
+        if format.is_some_and(|f| f.has_sprint()) {
+            results.push(source.results(&Sessions::Sprint, &event.meeting_key)?);
+        }
+        results.push(source.results(&Sessions::Race, &event.meeting_key)?);
+    }
+    Ok(compute_standings(&results))
+}
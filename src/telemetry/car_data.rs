@@ -0,0 +1,72 @@
+use std::{collections::HashMap, error::Error};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One `CarData.z` sample of a single car, sent at roughly 4 Hz.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CarDataSample {
+    pub utc: DateTime<Utc>,
+    pub rpm: u32,
+    /// km/h
+    pub speed: u32,
+    pub gear: u8,
+    /// 0 - 100
+    pub throttle: u8,
+    /// 0 or 100, the feed does not carry brake pressure.
+    pub brake: u8,
+    /// Raw DRS channel, see [`drs_open`].
+    pub drs: u8,
+}
+
+/// The DRS channel uses 10, 12 and 14 for an open flap, 8 for eligible and lower for off.
+pub fn drs_open(drs: u8) -> bool {
+    drs >= 10
+}
+
+#[derive(Deserialize)]
+struct RawCarData {
+    #[serde(rename = "Entries")]
+    entries: Vec<RawCarDataEntry>,
+}
+
+#[derive(Deserialize)]
+struct RawCarDataEntry {
+    #[serde(rename = "Utc")]
+    utc: DateTime<Utc>,
+    #[serde(rename = "Cars")]
+    cars: HashMap<String, RawCar>,
+}
+
+#[derive(Deserialize)]
+struct RawCar {
+    #[serde(rename = "Channels")]
+    channels: HashMap<String, i64>,
+}
+
+/// Parses a decoded `CarData.z` payload into samples per racing number.
+pub fn parse_car_data(
+    payload: Value,
+) -> Result<HashMap<String, Vec<CarDataSample>>, Box<dyn Error>> {
+    let raw: RawCarData = serde_json::from_value(payload)?;
+    let mut samples: HashMap<String, Vec<CarDataSample>> = HashMap::new();
+    for entry in raw.entries {
+        for (racing_number, car) in entry.cars {
+            let channel = |c: &str| car.channels.get(c).copied().unwrap_or(0).max(0);
+            samples
+                .entry(racing_number)
+                .or_default()
+                .push(CarDataSample {
+                    utc: entry.utc,
+                    rpm: channel("0") as u32,
+                    speed: channel("2") as u32,
+                    gear: channel("3") as u8,
+                    throttle: channel("4").min(100) as u8,
+                    brake: channel("5").min(100) as u8,
+                    drs: channel("45") as u8,
+                });
+        }
+    }
+    Ok(samples)
+}
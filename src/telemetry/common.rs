@@ -1,3 +1,80 @@
-trait DataStream {}
+use std::{error::Error, io::Read};
 
-trait ParsedDataStream {}
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, Utc};
+use flate2::read::DeflateDecoder;
+use serde_json::Value;
+
+/// A single message of a topic (e.g. `TimingData`, `CarData.z`), as received from the feed.
+#[derive(Debug, Clone)]
+pub struct StreamMessage {
+    pub topic: String,
+    pub timestamp: DateTime<Utc>,
+    pub data: Value,
+}
+
+impl StreamMessage {
+    /// Topic name without the `.z` suffix of compressed topics.
+    pub fn topic_name(&self) -> &str {
+        self.topic.trim_end_matches(".z")
+    }
+
+    /// The payload of the message, compressed topics are inflated first.
+    pub fn payload(&self) -> Result<Value, Box<dyn Error>> {
+        match (&self.data, self.topic.ends_with(".z")) {
+            (Value::String(s), true) => decode_compressed(s),
+            (data, _) => Ok(data.clone()),
+        }
+    }
+}
+
+/// Source of raw topic messages, either a live connection or a recording.
+pub trait DataStream {
+    /// Returns the next message, `None` once the stream has ended.
+    fn next_message(&mut self) -> Option<Result<StreamMessage, Box<dyn Error>>>;
+}
+
+pub trait ParsedDataStream {}
+
+/// Compressed topics (`CarData.z`, `Position.z`) are raw deflate, base64 encoded.
+pub fn decode_compressed(data: &str) -> Result<Value, Box<dyn Error>> {
+    let bytes = general_purpose::STANDARD.decode(data.trim_matches('"'))?;
+    let mut buf = String::new();
+    DeflateDecoder::new(&bytes[..]).read_to_string(&mut buf)?;
+    Ok(serde_json::from_str(&buf)?)
+}
+
+/// Base url of the recorded sessions, session paths look like
+/// `2023/2023-07-09_British_Grand_Prix/2023-07-09_Race/`.
+pub const STATIC_URL: &str = "https://livetiming.formula1.com/static/";
+
+/// Applies a partial update of a topic to its current state. Objects are merged key by key,
+/// lists are updated with objects keyed by index, anything else replaces the old value.
+pub fn merge_update(state: &mut Value, update: Value) {
+    match (state, update) {
+        (Value::Object(state), Value::Object(update)) => {
+            for (key, value) in update {
+                match state.get_mut(&key) {
+                    Some(existing) => merge_update(existing, value),
+                    None => {
+                        state.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(state), Value::Object(update)) => {
+            let mut update: Vec<(usize, Value)> = update
+                .into_iter()
+                .filter_map(|(k, v)| Some((k.parse().ok()?, v)))
+                .collect();
+            update.sort_by_key(|(i, _)| *i);
+            for (i, value) in update {
+                if i >= state.len() {
+                    state.resize(i + 1, Value::Object(Default::default()));
+                }
+                merge_update(&mut state[i], value);
+            }
+        }
+        (state, update) => *state = update,
+    }
+}
@@ -0,0 +1,62 @@
+use std::{collections::HashMap, error::Error};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::common::merge_update;
+
+/// An entry of the `DriverList` topic.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Driver {
+    #[serde(rename = "RacingNumber")]
+    pub racing_number: String,
+    #[serde(rename = "Tla")]
+    pub tla: String,
+    #[serde(rename = "FullName", default)]
+    pub full_name: String,
+    #[serde(rename = "TeamName", default)]
+    pub team_name: String,
+    #[serde(rename = "TeamColour", default)]
+    pub team_colour: String,
+    #[serde(rename = "Line", default)]
+    pub line: u32,
+}
+
+/// The `DriverList` topic, drivers keyed by racing number.
+#[derive(Debug, Clone, Default)]
+pub struct DriverList {
+    raw: Value,
+    pub drivers: HashMap<String, Driver>,
+}
+
+impl DriverList {
+    pub fn apply(&mut self, update: Value) -> Result<(), Box<dyn Error>> {
+        if self.raw.is_null() {
+            self.raw = Value::Object(Default::default());
+        }
+        merge_update(&mut self.raw, update);
+        self.drivers.clear();
+        for (racing_number, driver) in self.raw.as_object().into_iter().flatten() {
+            // `_kf` and similar bookkeeping keys are not drivers.
+            if driver.get("Tla").is_none() {
+                continue;
+            }
+            self.drivers.insert(
+                racing_number.clone(),
+                serde_json::from_value(driver.clone())?,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn tla(&self, racing_number: &str) -> Option<&str> {
+        self.drivers.get(racing_number).map(|d| d.tla.as_str())
+    }
+
+    pub fn racing_number(&self, tla: &str) -> Option<&str> {
+        self.drivers
+            .values()
+            .find(|d| d.tla.eq_ignore_ascii_case(tla))
+            .map(|d| d.racing_number.as_str())
+    }
+}
@@ -1 +1,149 @@
+use std::{collections::VecDeque, error::Error, net::TcpStream};
 
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use tungstenite::{client::IntoClientRequest, stream::MaybeTlsStream, Message, WebSocket};
+
+use super::common::{DataStream, StreamMessage};
+
+const SIGNALR_URL: &str = "livetiming.formula1.com/signalr";
+const CONNECTION_DATA: &str = r#"[{"name":"Streaming"}]"#;
+
+/// Every topic of the live timing feed.
+pub const TOPICS: &[&str] = &[
+    "Heartbeat",
+    "CarData.z",
+    "Position.z",
+    "ExtrapolatedClock",
+    "TopThree",
+    "TimingStats",
+    "TimingAppData",
+    "WeatherData",
+    "TrackStatus",
+    "DriverList",
+    "RaceControlMessages",
+    "SessionInfo",
+    "SessionData",
+    "SessionStatus",
+    "LapCount",
+    "TimingData",
+    "TeamRadio",
+    "PitLaneTimeCollection",
+];
+
+/// Live timing over the SignalR (1.5) websocket of livetiming.formula1.com.
+pub struct Live {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    pending: VecDeque<StreamMessage>,
+}
+
+impl Live {
+    /// Connects and subscribes to `topics`, the first messages hold the current state of
+    /// every topic.
+    pub fn connect(topics: &[&str]) -> Result<Self, Box<dyn Error>> {
+        let connection_data = url_encode(CONNECTION_DATA);
+        let negotiate = reqwest::blocking::get(format!(
+            "https://{SIGNALR_URL}/negotiate?clientProtocol=1.5&connectionData={connection_data}"
+        ))?
+        .error_for_status()?;
+        let cookie: Vec<String> = negotiate
+            .headers()
+            .get_all("set-cookie")
+            .iter()
+            .filter_map(|c| c.to_str().ok()?.split(';').next().map(str::to_string))
+            .collect();
+        let body: Value = serde_json::from_str(&negotiate.text()?)?;
+        let token = body
+            .get("ConnectionToken")
+            .and_then(Value::as_str)
+            .ok_or("Key `ConnectionToken` not in received json.")?;
+
+        let mut request = format!(
+            "wss://{SIGNALR_URL}/connect?clientProtocol=1.5&transport=webSockets&connectionToken={}&connectionData={connection_data}",
+            url_encode(token)
+        )
+        .into_client_request()?;
+        let headers = request.headers_mut();
+        headers.insert("User-Agent", "BestHTTP".parse()?);
+        headers.insert("Accept-Encoding", "gzip,identity".parse()?);
+        if !cookie.is_empty() {
+            headers.insert("Cookie", cookie.join("; ").parse()?);
+        }
+        let (mut socket, _) = tungstenite::connect(request)?;
+        let subscribe = json!({"H": "Streaming", "M": "Subscribe", "A": [topics], "I": 1});
+        socket.send(Message::Text(subscribe.to_string()))?;
+        Ok(Live {
+            socket,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Unpacks a SignalR frame into topic messages.
+    fn receive(&mut self, frame: &str) -> Result<(), Box<dyn Error>> {
+        let frame: Value = serde_json::from_str(frame)?;
+        // Answer to the subscription, the current state of every topic.
+        if let Some(Value::Object(state)) = frame.get("R") {
+            let now = Utc::now();
+            for (topic, data) in state {
+                self.pending.push_back(StreamMessage {
+                    topic: topic.clone(),
+                    timestamp: now,
+                    data: data.clone(),
+                });
+            }
+        }
+        for feed in frame
+            .get("M")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some([topic, data, timestamp]) = feed
+                .get("A")
+                .and_then(Value::as_array)
+                .and_then(|a| <&[Value; 3]>::try_from(a.as_slice()).ok())
+            else {
+                continue;
+            };
+            self.pending.push_back(StreamMessage {
+                topic: topic.as_str().unwrap_or_default().to_string(),
+                timestamp: timestamp
+                    .as_str()
+                    .and_then(|t| t.parse::<DateTime<Utc>>().ok())
+                    .unwrap_or_else(Utc::now),
+                data: data.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl DataStream for Live {
+    fn next_message(&mut self) -> Option<Result<StreamMessage, Box<dyn Error>>> {
+        while self.pending.is_empty() {
+            match self.socket.read() {
+                Ok(Message::Text(frame)) => {
+                    if let Err(e) = self.receive(&frame) {
+                        return Some(Err(e));
+                    }
+                }
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => {}
+                Err(tungstenite::Error::ConnectionClosed) => return None,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
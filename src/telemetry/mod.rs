@@ -1,3 +1,14 @@
-mod common;
-mod live;
-mod playback;
+pub mod car_data;
+pub mod common;
+pub mod driver_list;
+pub mod live;
+pub mod playback;
+pub mod position;
+pub mod race_control;
+pub mod relay;
+pub mod stints;
+pub mod team_radio;
+pub mod timing;
+pub mod trace;
+pub mod track_map;
+pub mod weather;
@@ -1 +1,92 @@
+use std::{collections::VecDeque, error::Error, fs, path::Path};
 
+use chrono::{DateTime, Duration, Utc};
+
+use super::common::{DataStream, StreamMessage};
+
+/// Replays a recorded session, a directory of `<Topic>.jsonStream` files.
+pub struct Playback {
+    messages: VecDeque<StreamMessage>,
+}
+
+impl Playback {
+    /// Reads every `.jsonStream` file in `dir`, messages are ordered by time across topics.
+    /// The offsets in the files are relative to `session_start`.
+    pub fn from_dir(dir: &Path, session_start: DateTime<Utc>) -> Result<Self, Box<dyn Error>> {
+        Self::from_dir_topics(dir, session_start, &[])
+    }
+
+    /// Like [`Playback::from_dir`], but only reads `topics` (all of them when empty). The
+    /// compressed topics are by far the largest files of a recording.
+    pub fn from_dir_topics(
+        dir: &Path,
+        session_start: DateTime<Utc>,
+        topics: &[&str],
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut messages = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(topic) = file_name.strip_suffix(".jsonStream") else {
+                continue;
+            };
+            if !topics.is_empty() && !topics.contains(&topic) {
+                continue;
+            }
+            let content = fs::read_to_string(&path)?;
+            messages.extend(read_json_stream(topic, &content, session_start)?);
+        }
+        Ok(Self::from_messages(messages))
+    }
+
+    pub fn from_messages(mut messages: Vec<StreamMessage>) -> Self {
+        messages.sort_by_key(|m| m.timestamp);
+        Playback {
+            messages: messages.into(),
+        }
+    }
+}
+
+impl DataStream for Playback {
+    fn next_message(&mut self) -> Option<Result<StreamMessage, Box<dyn Error>>> {
+        self.messages.pop_front().map(Ok)
+    }
+}
+
+/// Parses the content of a `<Topic>.jsonStream` file, lines look like `00:01:02.345{"Lines":...}`.
+pub fn read_json_stream(
+    topic: &str,
+    content: &str,
+    session_start: DateTime<Utc>,
+) -> Result<Vec<StreamMessage>, Box<dyn Error>> {
+    let mut messages = Vec::new();
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (offset, data) = line
+            .split_at_checked(12)
+            .ok_or(format!("Line `{line}` is too short to hold a timestamp."))?;
+        messages.push(StreamMessage {
+            topic: topic.to_string(),
+            timestamp: session_start + parse_offset(offset)?,
+            data: serde_json::from_str(data)?,
+        });
+    }
+    Ok(messages)
+}
+
+/// `HH:MM:SS.mmm` to a duration.
+fn parse_offset(offset: &str) -> Result<Duration, Box<dyn Error>> {
+    let err = || format!("Invalid stream offset `{offset}`.");
+    let mut parts = offset.split(':');
+    let hours: i64 = parts.next().ok_or_else(err)?.parse()?;
+    let minutes: i64 = parts.next().ok_or_else(err)?.parse()?;
+    let seconds: f64 = parts.next().ok_or_else(err)?.parse()?;
+    Ok(Duration::hours(hours)
+        + Duration::minutes(minutes)
+        + Duration::milliseconds((seconds * 1000.0).round() as i64))
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashMap, error::Error};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionStatus {
+    OnTrack,
+    OffTrack,
+}
+
+/// One `Position.z` sample of a single car, coordinates are in 1/10 m.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PositionSample {
+    pub utc: DateTime<Utc>,
+    pub status: PositionStatus,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Deserialize)]
+struct RawPositionData {
+    #[serde(rename = "Position")]
+    position: Vec<RawPositionEntry>,
+}
+
+#[derive(Deserialize)]
+struct RawPositionEntry {
+    #[serde(rename = "Timestamp")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "Entries")]
+    entries: HashMap<String, RawPosition>,
+}
+
+#[derive(Deserialize)]
+struct RawPosition {
+    #[serde(rename = "Status")]
+    status: PositionStatus,
+    #[serde(rename = "X")]
+    x: f64,
+    #[serde(rename = "Y")]
+    y: f64,
+    #[serde(rename = "Z")]
+    z: f64,
+}
+
+/// Parses a decoded `Position.z` payload into samples per racing number.
+pub fn parse_position(
+    payload: Value,
+) -> Result<HashMap<String, Vec<PositionSample>>, Box<dyn Error>> {
+    let raw: RawPositionData = serde_json::from_value(payload)?;
+    let mut samples: HashMap<String, Vec<PositionSample>> = HashMap::new();
+    for entry in raw.position {
+        for (racing_number, pos) in entry.entries {
+            samples
+                .entry(racing_number)
+                .or_default()
+                .push(PositionSample {
+                    utc: entry.timestamp,
+                    status: pos.status,
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                });
+        }
+    }
+    Ok(samples)
+}
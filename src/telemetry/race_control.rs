@@ -0,0 +1,310 @@
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::common::{DataStream, StreamMessage};
+use crate::weekend_common::custom_date_format;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Flag,
+    Drs,
+    SafetyCar,
+    CarEvent,
+    Other,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    #[serde(rename = "GREEN")]
+    Green,
+    #[serde(rename = "CLEAR")]
+    Clear,
+    #[serde(rename = "YELLOW")]
+    Yellow,
+    #[serde(rename = "DOUBLE YELLOW")]
+    DoubleYellow,
+    #[serde(rename = "RED")]
+    Red,
+    #[serde(rename = "CHEQUERED")]
+    Chequered,
+    #[serde(rename = "BLUE")]
+    Blue,
+    #[serde(rename = "BLACK AND WHITE")]
+    BlackAndWhite,
+    #[serde(rename = "BLACK AND ORANGE")]
+    BlackAndOrange,
+    #[serde(rename = "BLACK")]
+    Black,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Track,
+    Sector,
+    Driver,
+    #[serde(other)]
+    Unknown,
+}
+
+/// An entry of the `RaceControlMessages` topic.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RaceControlMessage {
+    #[serde(with = "custom_date_format")]
+    #[serde(rename = "Utc")]
+    pub utc: DateTime<Utc>,
+    #[serde(rename = "Lap")]
+    pub lap: Option<u32>,
+    #[serde(rename = "Category")]
+    pub category: Category,
+    #[serde(rename = "Flag")]
+    pub flag: Option<Flag>,
+    #[serde(rename = "Scope")]
+    pub scope: Option<Scope>,
+    #[serde(rename = "Sector")]
+    pub sector: Option<u32>,
+    #[serde(rename = "RacingNumber")]
+    pub racing_number: Option<String>,
+    /// `ENABLED`/`DISABLED` for DRS, `DEPLOYED`/`ENDING` for the safety car.
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+    /// `SAFETY CAR` or `VIRTUAL SAFETY CAR`.
+    #[serde(rename = "Mode")]
+    pub mode: Option<String>,
+    #[serde(rename = "Message")]
+    pub message: String,
+}
+
+/// What a race control message means, as far as it can be told from its fields and text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RaceControlEvent {
+    Flag {
+        flag: Flag,
+        scope: Option<Scope>,
+        sector: Option<u32>,
+        racing_number: Option<String>,
+    },
+    SafetyCarDeployed,
+    SafetyCarEnding,
+    VirtualSafetyCarDeployed,
+    VirtualSafetyCarEnding,
+    DrsEnabled,
+    DrsDisabled,
+    Penalty {
+        racing_number: Option<String>,
+        penalty: String,
+    },
+    LapTimeDeleted {
+        racing_number: Option<String>,
+        time: Option<String>,
+    },
+    Other,
+}
+
+impl RaceControlMessage {
+    pub fn event(&self) -> RaceControlEvent {
+        let text = self.message.to_uppercase();
+        let status = self.status.as_deref().unwrap_or_default();
+        match self.category {
+            Category::Flag => RaceControlEvent::Flag {
+                flag: self.flag.unwrap_or(Flag::Unknown),
+                scope: self.scope,
+                sector: self.sector,
+                racing_number: self.racing_number.clone(),
+            },
+            Category::Drs if status == "ENABLED" || text.contains("DRS ENABLED") => {
+                RaceControlEvent::DrsEnabled
+            }
+            Category::Drs => RaceControlEvent::DrsDisabled,
+            Category::SafetyCar => {
+                let virtual_sc = self.mode.as_deref() == Some("VIRTUAL SAFETY CAR")
+                    || text.contains("VIRTUAL SAFETY CAR");
+                let ending = status.contains("ENDING") || text.contains("ENDING");
+                match (virtual_sc, ending) {
+                    (true, true) => RaceControlEvent::VirtualSafetyCarEnding,
+                    (true, false) => RaceControlEvent::VirtualSafetyCarDeployed,
+                    (false, true) => RaceControlEvent::SafetyCarEnding,
+                    (false, false) => RaceControlEvent::SafetyCarDeployed,
+                }
+            }
+            _ if text.contains("DELETED") => RaceControlEvent::LapTimeDeleted {
+                racing_number: self.racing_number.clone().or_else(|| car_number(&text)),
+                time: text
+                    .split_once("TIME ")
+                    .and_then(|(_, rest)| rest.split_whitespace().next())
+                    .map(str::to_string),
+            },
+            _ if text.contains("PENALTY") && !text.contains("NO FURTHER") => {
+                RaceControlEvent::Penalty {
+                    racing_number: self.racing_number.clone().or_else(|| car_number(&text)),
+                    penalty: text
+                        .split(" FOR ")
+                        .next()
+                        .unwrap_or_default()
+                        .trim_start_matches("FIA STEWARDS: ")
+                        .to_string(),
+                }
+            }
+            _ => RaceControlEvent::Other,
+        }
+    }
+}
+
+/// `CAR 44 (HAM) ...` to `44`.
+fn car_number(text: &str) -> Option<String> {
+    let (_, rest) = text.split_once("CAR ")?;
+    let number: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    (!number.is_empty()).then_some(number)
+}
+
+/// Status codes of the `TrackStatus` topic.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackStatus {
+    #[serde(rename = "1")]
+    AllClear,
+    #[serde(rename = "2")]
+    Yellow,
+    #[serde(rename = "4")]
+    SafetyCarDeployed,
+    #[serde(rename = "5")]
+    Red,
+    #[serde(rename = "6")]
+    VirtualSafetyCarDeployed,
+    #[serde(rename = "7")]
+    VirtualSafetyCarEnding,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionStatus {
+    Inactive,
+    Started,
+    Aborted,
+    Finished,
+    Finalised,
+    Ends,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Race control, track status and session status of a session, in order of arrival.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RaceControlTimeline {
+    pub messages: Vec<RaceControlMessage>,
+    pub track_status: Vec<(DateTime<Utc>, TrackStatus)>,
+    pub session_status: Vec<(DateTime<Utc>, SessionStatus)>,
+}
+
+impl RaceControlTimeline {
+    /// Reads the whole stream, ignoring topics other than race control.
+    pub fn from_stream(stream: &mut impl DataStream) -> Result<Self, Box<dyn Error>> {
+        let mut timeline = RaceControlTimeline::default();
+        while let Some(message) = stream.next_message() {
+            timeline.push(&message?)?;
+        }
+        Ok(timeline)
+    }
+
+    /// Adds a message of the stream, returns whether it was a race control topic.
+    pub fn push(&mut self, message: &StreamMessage) -> Result<bool, Box<dyn Error>> {
+        match message.topic_name() {
+            "RaceControlMessages" => {
+                // The first message holds a list, updates a map keyed by index.
+                let messages = match message.payload()?.get_mut("Messages").map(Value::take) {
+                    Some(Value::Array(list)) => list,
+                    Some(Value::Object(map)) => {
+                        let mut indexed: Vec<(u64, Value)> = map
+                            .into_iter()
+                            .map(|(k, v)| (k.parse().unwrap_or(u64::MAX), v))
+                            .collect();
+                        indexed.sort_by_key(|(k, _)| *k);
+                        indexed.into_iter().map(|(_, v)| v).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                for m in messages {
+                    self.messages.push(serde_json::from_value(m)?);
+                }
+            }
+            "TrackStatus" => {
+                let status = message
+                    .payload()?
+                    .get_mut("Status")
+                    .ok_or("Key `Status` not in TrackStatus.")?
+                    .take();
+                self.track_status
+                    .push((message.timestamp, serde_json::from_value(status)?));
+            }
+            "SessionStatus" => {
+                let status = message
+                    .payload()?
+                    .get_mut("Status")
+                    .ok_or("Key `Status` not in SessionStatus.")?
+                    .take();
+                self.session_status
+                    .push((message.timestamp, serde_json::from_value(status)?));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn track_status_at(&self, time: DateTime<Utc>) -> Option<TrackStatus> {
+        last_before(&self.track_status, time)
+    }
+
+    pub fn session_status_at(&self, time: DateTime<Utc>) -> Option<SessionStatus> {
+        last_before(&self.session_status, time)
+    }
+
+    /// Whether DRS was enabled at `time`, it is disabled until race control enables it.
+    pub fn drs_enabled_at(&self, time: DateTime<Utc>) -> bool {
+        self.events_between(DateTime::<Utc>::MIN_UTC, time)
+            .filter_map(|(_, e)| match e {
+                RaceControlEvent::DrsEnabled => Some(true),
+                RaceControlEvent::DrsDisabled => Some(false),
+                _ => None,
+            })
+            .last()
+            .unwrap_or(false)
+    }
+
+    /// Events of the messages sent in `[from, to]`.
+    pub fn events_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> impl Iterator<Item = (&RaceControlMessage, RaceControlEvent)> {
+        self.messages
+            .iter()
+            .filter(move |m| from <= m.utc && m.utc <= to)
+            .map(|m| (m, m.event()))
+    }
+
+    pub fn penalties(&self) -> Vec<(&RaceControlMessage, RaceControlEvent)> {
+        self.events_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+            .filter(|(_, e)| matches!(e, RaceControlEvent::Penalty { .. }))
+            .collect()
+    }
+
+    pub fn deleted_lap_times(&self) -> Vec<(&RaceControlMessage, RaceControlEvent)> {
+        self.events_between(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+            .filter(|(_, e)| matches!(e, RaceControlEvent::LapTimeDeleted { .. }))
+            .collect()
+    }
+}
+
+fn last_before<T: Copy>(timeline: &[(DateTime<Utc>, T)], time: DateTime<Utc>) -> Option<T> {
+    timeline
+        .iter()
+        .take_while(|(t, _)| *t <= time)
+        .last()
+        .map(|(_, s)| *s)
+}
@@ -0,0 +1,263 @@
+use std::{
+    error::Error,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, SyncSender},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tungstenite::{Message, WebSocket};
+
+use super::{
+    common::{DataStream, StreamMessage},
+    timing::TimingState,
+};
+
+/// Clients that don't finish their request or take a write within this time are dropped.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request head taken from a client, browsers send well under this.
+const MAX_HEAD: usize = 8 * 1024;
+
+/// Events queued for a client that falls behind. Every client has its own writer, a full queue
+/// drops the client instead of holding up the others.
+const QUEUE: usize = 1024;
+
+/// What the relay sends, as json with a `type` field. Over server-sent events the type is also
+/// the event name.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayEvent<'a> {
+    /// Full state, sent on connect and every snapshot interval.
+    Snapshot {
+        timestamp: DateTime<Utc>,
+        state: &'a TimingState,
+    },
+    /// A single message, compressed topics are already inflated and have their `.z` dropped.
+    Message {
+        topic: &'a str,
+        timestamp: DateTime<Utc>,
+        data: Value,
+    },
+}
+
+impl RelayEvent<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            RelayEvent::Snapshot { .. } => "snapshot",
+            RelayEvent::Message { .. } => "message",
+        }
+    }
+}
+
+enum Client {
+    WebSocket(Box<WebSocket<TcpStream>>),
+    Sse(TcpStream),
+}
+
+impl Client {
+    /// Writes the queue out until it closes or a write fails.
+    fn spawn_writer(mut self) -> SyncSender<Arc<(String, String)>> {
+        let (sender, queue) = mpsc::sync_channel::<Arc<(String, String)>>(QUEUE);
+        thread::spawn(move || {
+            for event in queue {
+                let (name, json) = &*event;
+                if self.send(name, json).is_err() {
+                    break;
+                }
+            }
+        });
+        sender
+    }
+
+    fn send(&mut self, name: &str, json: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            Client::WebSocket(socket) => socket.send(Message::Text(json.to_string()))?,
+            Client::Sse(stream) => {
+                stream.write_all(format!("event: {name}\ndata: {json}\n\n").as_bytes())?;
+                stream.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    state: TimingState,
+    last: Option<DateTime<Utc>>,
+    clients: Vec<SyncSender<Arc<(String, String)>>>,
+}
+
+impl Shared {
+    fn snapshot(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_json::to_string(&RelayEvent::Snapshot {
+            timestamp: self.last.unwrap_or_else(Utc::now),
+            state: &self.state,
+        })?)
+    }
+
+    /// Queues for every client, the ones that are gone or fell too far behind are dropped.
+    fn send_all(&mut self, name: &str, json: &str) {
+        let event = Arc::new((name.to_string(), json.to_string()));
+        self.clients
+            .retain(|client| client.try_send(event.clone()).is_ok());
+    }
+}
+
+/// Re-broadcasts one live or recorded stream to any number of clients, over websockets or
+/// server-sent events (any other request). Late joiners get a full snapshot before the
+/// messages.
+pub struct Relay {
+    addr: SocketAddr,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Relay {
+    /// Starts accepting clients on `addr`, like `127.0.0.1:8081`.
+    pub fn bind(addr: &str) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let accepting = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = accepting.clone();
+                // The handshake waits on the client, keep it off the accept loop.
+                thread::spawn(move || {
+                    let _ = accept(stream, &shared);
+                });
+            }
+        });
+        Ok(Relay { addr, shared })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn clients(&self) -> usize {
+        self.shared.lock().map(|s| s.clients.len()).unwrap_or(0)
+    }
+
+    /// Relays `stream` until it ends. With `speed` the messages are paced by their timestamps,
+    /// for replays. A snapshot goes out every `snapshot_interval` so clients can resync.
+    /// Messages that can't be applied are skipped, returns how many.
+    pub fn run(
+        &self,
+        stream: &mut impl DataStream,
+        speed: Option<f64>,
+        snapshot_interval: Duration,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut last_snapshot = Instant::now();
+        let mut previous: Option<DateTime<Utc>> = None;
+        let mut skipped = 0;
+        while let Some(message) = stream.next_message() {
+            let message = message?;
+            if let (Some(speed), Some(previous)) = (speed, previous) {
+                if let Ok(wait) = (message.timestamp - previous).to_std() {
+                    thread::sleep(wait.div_f64(speed));
+                }
+            }
+            previous = Some(message.timestamp);
+            if self.push(&message).is_err() {
+                skipped += 1;
+            }
+            if last_snapshot.elapsed() >= snapshot_interval {
+                let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+                let json = shared.snapshot()?;
+                shared.send_all("snapshot", &json);
+                last_snapshot = Instant::now();
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// Applies a single message and sends it to every client.
+    pub fn push(&self, message: &StreamMessage) -> Result<(), Box<dyn Error>> {
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        shared.state.apply(message)?;
+        shared.last = Some(message.timestamp);
+        let event = RelayEvent::Message {
+            topic: message.topic_name(),
+            timestamp: message.timestamp,
+            data: message.payload()?,
+        };
+        shared.send_all(event.name(), &serde_json::to_string(&event)?);
+        Ok(())
+    }
+}
+
+/// Sets up a new client. The snapshot is queued under the same lock the messages are broadcast
+/// with, so nothing is missed or sent twice in between.
+fn accept(stream: TcpStream, shared: &Mutex<Shared>) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + TIMEOUT;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let client = if is_websocket(&stream, deadline)? {
+        Client::WebSocket(Box::new(tungstenite::accept(stream)?))
+    } else {
+        let mut stream = stream;
+        read_request_head(&mut stream, deadline)?;
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+              Access-Control-Allow-Origin: *\r\nConnection: keep-alive\r\n\r\n",
+        )?;
+        Client::Sse(stream)
+    };
+    let mut shared = shared.lock().map_err(|e| e.to_string())?;
+    let snapshot = Arc::new(("snapshot".to_string(), shared.snapshot()?));
+    let sender = client.spawn_writer();
+    sender.try_send(snapshot)?;
+    shared.clients.push(sender);
+    Ok(())
+}
+
+/// Looks at the request without consuming it, the websocket handshake needs to read it again.
+/// Peeking returns what already arrived right away, so it polls until `deadline`.
+fn is_websocket(stream: &TcpStream, deadline: Instant) -> Result<bool, Box<dyn Error>> {
+    let mut buf = [0; MAX_HEAD];
+    loop {
+        let n = stream.peek(&mut buf)?;
+        let head = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+        if head.contains("\r\n\r\n") || n == buf.len() {
+            return Ok(head.contains("upgrade: websocket"));
+        }
+        if n == 0 {
+            return Err("Connection closed before the request was complete.".into());
+        }
+        if Instant::now() >= deadline {
+            return Err("Request not complete in time.".into());
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Reads up to the end of the request head, at most [`MAX_HEAD`] bytes by `deadline`, so a
+/// client trickling bytes can't hold on to its thread.
+fn read_request_head(stream: &mut TcpStream, deadline: Instant) -> Result<(), Box<dyn Error>> {
+    let mut head = Vec::new();
+    let mut byte = [0];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD {
+            return Err("Request head too large.".into());
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err("Request not complete in time.".into());
+        }
+        stream.set_read_timeout(Some(left))?;
+        if stream.read(&mut byte)? == 0 {
+            return Err("Connection closed before the request was complete.".into());
+        }
+        head.push(byte[0]);
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    common::{merge_update, DataStream, StreamMessage},
+    driver_list::DriverList,
+};
+use crate::results::RaceResult;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compound {
+    #[serde(rename = "SOFT")]
+    Soft,
+    #[serde(rename = "MEDIUM")]
+    Medium,
+    #[serde(rename = "HARD")]
+    Hard,
+    #[serde(rename = "INTERMEDIATE")]
+    Intermediate,
+    #[serde(rename = "WET")]
+    Wet,
+    #[serde(rename = "TEST_UNKNOWN", alias = "TEST")]
+    Test,
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for Compound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Compound::Soft => "S",
+            Compound::Medium => "M",
+            Compound::Hard => "H",
+            Compound::Intermediate => "I",
+            Compound::Wet => "W",
+            Compound::Test | Compound::Unknown => "?",
+        };
+        f.write_str(letter)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Stint {
+    pub compound: Compound,
+    pub new: bool,
+    /// First and last lap of the race driven on this set, `end_lap` is still counting while
+    /// the stint is ongoing.
+    pub start_lap: u32,
+    pub end_lap: u32,
+    /// Laps on the set at the start of the stint, used sets arrive with some.
+    pub tyre_age_at_start: u32,
+}
+
+impl Stint {
+    pub fn laps(&self) -> u32 {
+        (self.end_lap + 1).saturating_sub(self.start_lap)
+    }
+
+    pub fn tyre_age_at_end(&self) -> u32 {
+        self.tyre_age_at_start + self.laps()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PitStop {
+    pub in_lap: u32,
+    pub out_lap: u32,
+    /// Seconds from pit entry to pit exit, from `PitLaneTimeCollection`.
+    pub pit_lane_time: Option<f64>,
+    /// Seconds stationary in the box, from `PitStopSeries` (not sent for every season).
+    pub stationary_time: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DriverStrategy {
+    pub racing_number: String,
+    pub driver_tla: Option<String>,
+    pub stints: Vec<Stint>,
+    pub pit_stops: Vec<PitStop>,
+}
+
+impl DriverStrategy {
+    /// Compounds in order of use, e.g. `[Medium, Hard]`.
+    pub fn compounds(&self) -> Vec<Compound> {
+        self.stints.iter().map(|s| s.compound).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RaceStrategy {
+    pub drivers: Vec<DriverStrategy>,
+}
+
+impl RaceStrategy {
+    pub fn driver(&self, tla: &str) -> Option<&DriverStrategy> {
+        self.drivers
+            .iter()
+            .find(|d| d.driver_tla.as_deref() == Some(tla))
+    }
+
+    /// Every race result next to the strategy of that driver, joined by TLA.
+    pub fn join<'a>(
+        &'a self,
+        results: &'a [RaceResult],
+    ) -> Vec<(&'a RaceResult, Option<&'a DriverStrategy>)> {
+        results
+            .iter()
+            .map(|r| (r, self.driver(&r.driver_tla)))
+            .collect()
+    }
+}
+
+/// Collects `TimingAppData`, `DriverList`, `PitLaneTimeCollection` and `PitStopSeries`.
+#[derive(Debug, Default)]
+pub struct StintTracker {
+    timing_app_data: Value,
+    drivers: DriverList,
+    /// (racing number, lap) to pit lane time.
+    pit_lane_times: HashMap<(String, u32), f64>,
+    /// (racing number, lap) to stationary time.
+    stationary_times: HashMap<(String, u32), f64>,
+}
+
+impl StintTracker {
+    pub fn from_stream(stream: &mut impl DataStream) -> Result<Self, Box<dyn Error>> {
+        let mut tracker = StintTracker::default();
+        while let Some(message) = stream.next_message() {
+            tracker.push(&message?)?;
+        }
+        Ok(tracker)
+    }
+
+    pub fn push(&mut self, message: &StreamMessage) -> Result<bool, Box<dyn Error>> {
+        match message.topic_name() {
+            "TimingAppData" => {
+                if self.timing_app_data.is_null() {
+                    self.timing_app_data = Value::Object(Default::default());
+                }
+                merge_update(&mut self.timing_app_data, message.payload()?);
+            }
+            "DriverList" => self.drivers.apply(message.payload()?)?,
+            "PitLaneTimeCollection" => {
+                let payload = message.payload()?;
+                for (racing_number, pit) in payload
+                    .get("PitTimes")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                {
+                    if let (Some(lap), Some(duration)) =
+                        (number(pit.get("Lap")), number(pit.get("Duration")))
+                    {
+                        self.pit_lane_times
+                            .insert((racing_number.clone(), lap as u32), duration);
+                    }
+                }
+            }
+            "PitStopSeries" => {
+                let payload = message.payload()?;
+                for stops in payload
+                    .get("PitTimes")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flat_map(|m| m.values())
+                {
+                    let stops: Vec<&Value> = match stops {
+                        Value::Array(list) => list.iter().collect(),
+                        Value::Object(map) => map.values().collect(),
+                        _ => Vec::new(),
+                    };
+                    for stop in stops.into_iter().filter_map(|s| s.get("PitStop")) {
+                        let racing_number = stop.get("RacingNumber").and_then(Value::as_str);
+                        let lap = number(stop.get("Lap"));
+                        if let (Some(racing_number), Some(lap)) = (racing_number, lap) {
+                            let key = (racing_number.to_string(), lap as u32);
+                            if let Some(time) = number(stop.get("PitStopTime")) {
+                                self.stationary_times.insert(key.clone(), time);
+                            }
+                            if let Some(time) = number(stop.get("PitLaneTime")) {
+                                self.pit_lane_times.entry(key).or_insert(time);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn strategy(&self) -> RaceStrategy {
+        let mut drivers: Vec<DriverStrategy> = self
+            .timing_app_data
+            .get("Lines")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(racing_number, line)| self.driver_strategy(racing_number, line))
+            .collect();
+        drivers.sort_by_key(|d| d.racing_number.parse::<u32>().unwrap_or(u32::MAX));
+        RaceStrategy { drivers }
+    }
+
+    fn driver_strategy(&self, racing_number: &str, line: &Value) -> DriverStrategy {
+        let raw_stints: Vec<&Value> = match line.get("Stints") {
+            Some(Value::Array(list)) => list.iter().collect(),
+            Some(Value::Object(map)) => {
+                let mut indexed: Vec<(u32, &Value)> = map
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.parse().ok()?, v)))
+                    .collect();
+                indexed.sort_by_key(|(i, _)| *i);
+                indexed.into_iter().map(|(_, v)| v).collect()
+            }
+            _ => Vec::new(),
+        };
+        let mut stints: Vec<Stint> = Vec::new();
+        for raw in raw_stints {
+            let start_laps = number(raw.get("StartLaps")).unwrap_or(0.0) as u32;
+            let total_laps = number(raw.get("TotalLaps")).unwrap_or(0.0) as u32;
+            let start_lap = stints.last().map_or(1, |s| s.end_lap + 1);
+            stints.push(Stint {
+                compound: raw
+                    .get("Compound")
+                    .cloned()
+                    .and_then(|c| serde_json::from_value(c).ok())
+                    .unwrap_or(Compound::Unknown),
+                new: matches!(raw.get("New"), Some(Value::String(s)) if s == "true")
+                    || raw.get("New") == Some(&Value::Bool(true)),
+                start_lap,
+                end_lap: (start_lap + total_laps.saturating_sub(start_laps)).saturating_sub(1),
+                tyre_age_at_start: start_laps,
+            });
+        }
+        let pit_stops = stints
+            .windows(2)
+            .map(|pair| {
+                let in_lap = pair[0].end_lap;
+                let key = (racing_number.to_string(), in_lap);
+                // Pit lane timing is reported on either the in-lap or the out-lap.
+                let next_key = (racing_number.to_string(), in_lap + 1);
+                PitStop {
+                    in_lap,
+                    out_lap: pair[1].start_lap,
+                    pit_lane_time: self
+                        .pit_lane_times
+                        .get(&key)
+                        .or_else(|| self.pit_lane_times.get(&next_key))
+                        .copied(),
+                    stationary_time: self
+                        .stationary_times
+                        .get(&key)
+                        .or_else(|| self.stationary_times.get(&next_key))
+                        .copied(),
+                }
+            })
+            .collect();
+        DriverStrategy {
+            racing_number: racing_number.to_string(),
+            driver_tla: self.drivers.tla(racing_number).map(str::to_string),
+            stints,
+            pit_stops,
+        }
+    }
+}
+
+/// Numbers arrive both as json numbers and as strings.
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
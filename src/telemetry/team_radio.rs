@@ -0,0 +1,139 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    common::{DataStream, StreamMessage},
+    driver_list::DriverList,
+};
+use crate::utils::get_bytes;
+
+/// An entry of the `TeamRadio` topic, `path` is relative to the session path.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamRadioCapture {
+    #[serde(rename = "Utc")]
+    pub utc: DateTime<Utc>,
+    #[serde(rename = "RacingNumber")]
+    pub racing_number: String,
+    #[serde(rename = "Path")]
+    pub path: String,
+}
+
+/// A capture linked to the driver and the lap of the race it was sent in.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamRadioClip {
+    pub capture: TeamRadioCapture,
+    pub driver_tla: Option<String>,
+    pub lap: Option<u32>,
+}
+
+impl TeamRadioClip {
+    pub fn file_name(&self) -> &str {
+        self.capture
+            .path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.capture.path)
+    }
+}
+
+/// All team radio of a session, built from the `TeamRadio`, `DriverList` and `LapCount` topics.
+#[derive(Debug, Default)]
+pub struct TeamRadioIndex {
+    pub clips: Vec<TeamRadioClip>,
+    drivers: DriverList,
+    /// Start time of every lap, as announced by `LapCount`.
+    laps: Vec<(DateTime<Utc>, u32)>,
+}
+
+impl TeamRadioIndex {
+    pub fn from_stream(stream: &mut impl DataStream) -> Result<Self, Box<dyn Error>> {
+        let mut index = TeamRadioIndex::default();
+        while let Some(message) = stream.next_message() {
+            index.push(&message?)?;
+        }
+        Ok(index)
+    }
+
+    pub fn push(&mut self, message: &StreamMessage) -> Result<(), Box<dyn Error>> {
+        match message.topic_name() {
+            "DriverList" => {
+                self.drivers.apply(message.payload()?)?;
+                for clip in self.clips.iter_mut().filter(|c| c.driver_tla.is_none()) {
+                    clip.driver_tla = self
+                        .drivers
+                        .tla(&clip.capture.racing_number)
+                        .map(str::to_string);
+                }
+            }
+            "LapCount" => {
+                if let Some(lap) = message.payload()?.get("CurrentLap").and_then(Value::as_u64) {
+                    self.laps.push((message.timestamp, lap as u32));
+                }
+            }
+            "TeamRadio" => {
+                let captures = match message.payload()?.get_mut("Captures").map(Value::take) {
+                    Some(Value::Array(list)) => list,
+                    Some(Value::Object(map)) => map.into_iter().map(|(_, v)| v).collect(),
+                    _ => Vec::new(),
+                };
+                for capture in captures {
+                    let capture: TeamRadioCapture = serde_json::from_value(capture)?;
+                    self.clips.push(TeamRadioClip {
+                        driver_tla: self.drivers.tla(&capture.racing_number).map(str::to_string),
+                        lap: self.lap_at(capture.utc),
+                        capture,
+                    });
+                }
+                self.clips.sort_by_key(|c| c.capture.utc);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn lap_at(&self, utc: DateTime<Utc>) -> Option<u32> {
+        self.laps
+            .iter()
+            .take_while(|(t, _)| *t <= utc)
+            .last()
+            .map(|(_, lap)| *lap)
+    }
+
+    pub fn for_driver<'a>(&'a self, tla: &'a str) -> impl Iterator<Item = &'a TeamRadioClip> {
+        self.clips
+            .iter()
+            .filter(move |c| c.driver_tla.as_deref() == Some(tla))
+    }
+
+    /// Downloads every clip from `<base_url><session_path><path>` into `dir`, clips already
+    /// present are skipped. Clips are written to a `.part` file first and renamed when
+    /// complete, so an interrupted download is retried on the next run. Returns the local files
+    /// in the order of `clips`.
+    pub fn download(
+        &self,
+        base_url: &str,
+        session_path: &str,
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let mut files = Vec::new();
+        for clip in &self.clips {
+            let file = dir.join(clip.file_name());
+            if !file.exists() {
+                let url = format!("{base_url}{session_path}{}", clip.capture.path);
+                let part = dir.join(format!("{}.part", clip.file_name()));
+                fs::write(&part, get_bytes(&url)?)?;
+                fs::rename(&part, &file)?;
+            }
+            files.push(file);
+        }
+        Ok(files)
+    }
+}
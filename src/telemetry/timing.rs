@@ -0,0 +1,232 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    common::{merge_update, StreamMessage},
+    race_control::{RaceControlMessage, TrackStatus},
+    stints::Compound,
+};
+
+/// Colour of a lap or sector time on the timing screens.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeColor {
+    /// Fastest of everyone.
+    Purple,
+    /// Personal best.
+    Green,
+    Yellow,
+    /// Not set yet.
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimedValue {
+    pub value: String,
+    pub color: TimeColor,
+}
+
+impl TimedValue {
+    fn from_line(value: Option<&Value>) -> Self {
+        let text = value
+            .and_then(|v| v.get("Value"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let flag = |key| value.and_then(|v| v.get(key)).and_then(Value::as_bool) == Some(true);
+        let color = if text.is_empty() {
+            TimeColor::None
+        } else if flag("OverallFastest") {
+            TimeColor::Purple
+        } else if flag("PersonalFastest") {
+            TimeColor::Green
+        } else {
+            TimeColor::Yellow
+        };
+        TimedValue { value: text, color }
+    }
+}
+
+/// A line of the timing tower.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TowerRow {
+    pub position: u32,
+    pub racing_number: String,
+    pub driver_tla: String,
+    pub team_colour: String,
+    pub gap_to_leader: String,
+    pub interval: String,
+    pub laps: u32,
+    pub last_lap: TimedValue,
+    pub best_lap: String,
+    pub sectors: Vec<TimedValue>,
+    pub compound: Option<Compound>,
+    pub tyre_age: Option<u32>,
+    pub in_pit: bool,
+    pub pit_out: bool,
+    pub pit_stops: u32,
+    pub retired: bool,
+}
+
+/// Current state of every (uncompressed) topic, updated message by message. This is what the
+/// timing screens show at any moment.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct TimingState {
+    pub topics: Map<String, Value>,
+}
+
+impl TimingState {
+    /// Applies a message, compressed topics (`CarData.z`, `Position.z`) are skipped as they
+    /// carry samples rather than state.
+    pub fn apply(&mut self, message: &StreamMessage) -> Result<bool, Box<dyn Error>> {
+        if message.topic.ends_with(".z") {
+            return Ok(false);
+        }
+        let state = self
+            .topics
+            .entry(message.topic_name())
+            .or_insert(Value::Null);
+        merge_update(state, message.payload()?);
+        Ok(true)
+    }
+
+    pub fn topic(&self, name: &str) -> Option<&Value> {
+        self.topics.get(name)
+    }
+
+    fn line<'a>(&'a self, topic: &str, racing_number: &str) -> Option<&'a Value> {
+        self.topic(topic)?.get("Lines")?.get(racing_number)
+    }
+
+    /// `(current lap, total laps)` of a race.
+    pub fn lap_count(&self) -> Option<(u32, u32)> {
+        let lap_count = self.topic("LapCount")?;
+        let lap = |key| lap_count.get(key).and_then(Value::as_u64).map(|n| n as u32);
+        Some((lap("CurrentLap")?, lap("TotalLaps").unwrap_or_default()))
+    }
+
+    pub fn track_status(&self) -> Option<TrackStatus> {
+        let status = self.topic("TrackStatus")?.get("Status")?.clone();
+        serde_json::from_value(status).ok()
+    }
+
+    pub fn session_name(&self) -> Option<String> {
+        let info = self.topic("SessionInfo")?;
+        let meeting = info.get("Meeting")?.get("Name")?.as_str()?;
+        let session = info.get("Name").and_then(Value::as_str).unwrap_or_default();
+        Some(format!("{meeting} - {session}"))
+    }
+
+    pub fn race_control_messages(&self) -> Vec<RaceControlMessage> {
+        let messages = self
+            .topic("RaceControlMessages")
+            .and_then(|t| t.get("Messages"));
+        let messages: Vec<&Value> = match messages {
+            Some(Value::Array(list)) => list.iter().collect(),
+            // Keyed by index, "10" sorts before "2" as a string.
+            Some(Value::Object(map)) => {
+                let mut entries: Vec<(u64, &Value)> = map
+                    .iter()
+                    .map(|(i, m)| (i.parse().unwrap_or(u64::MAX), m))
+                    .collect();
+                entries.sort_by_key(|(i, _)| *i);
+                entries.into_iter().map(|(_, m)| m).collect()
+            }
+            _ => Vec::new(),
+        };
+        messages
+            .into_iter()
+            .filter_map(|m| serde_json::from_value(m.clone()).ok())
+            .collect()
+    }
+
+    /// The timing tower, ordered by position.
+    pub fn tower(&self) -> Vec<TowerRow> {
+        let Some(drivers) = self.topic("DriverList").and_then(Value::as_object) else {
+            return Vec::new();
+        };
+        let mut rows: Vec<TowerRow> = drivers
+            .iter()
+            .filter(|(_, d)| d.get("Tla").is_some())
+            .map(|(racing_number, driver)| self.row(racing_number, driver))
+            .collect();
+        rows.sort_by_key(|r| r.position);
+        rows
+    }
+
+    fn row(&self, racing_number: &str, driver: &Value) -> TowerRow {
+        let timing = self.line("TimingData", racing_number);
+        let text = |v: Option<&Value>| match v {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            Some(Value::Object(o)) => o
+                .get("Value")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            _ => String::new(),
+        };
+        let get = |key| timing.and_then(|t| t.get(key));
+        let flag = |key| get(key).and_then(Value::as_bool) == Some(true);
+        let position = text(get("Position"))
+            .parse()
+            .ok()
+            .or_else(|| driver.get("Line").and_then(Value::as_u64).map(|l| l as u32))
+            .unwrap_or(u32::MAX);
+        // Races have gaps, qualifying and practice the difference to the fastest lap.
+        let gap_to_leader = match text(get("GapToLeader")) {
+            gap if gap.is_empty() => text(get("TimeDiffToFastest")),
+            gap => gap,
+        };
+        let interval = match text(get("IntervalToPositionAhead")) {
+            interval if interval.is_empty() => text(get("TimeDiffToPositionAhead")),
+            interval => interval,
+        };
+        let sectors = match get("Sectors") {
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(|s| TimedValue::from_line(Some(s)))
+                .collect(),
+            Some(Value::Object(map)) => map
+                .values()
+                .map(|s| TimedValue::from_line(Some(s)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let stint = self
+            .line("TimingAppData", racing_number)
+            .and_then(|l| l.get("Stints"))
+            .and_then(|s| match s {
+                Value::Array(list) => list.last(),
+                Value::Object(map) => map
+                    .iter()
+                    .max_by_key(|(i, _)| i.parse::<u32>().unwrap_or(0))
+                    .map(|(_, stint)| stint),
+                _ => None,
+            });
+        TowerRow {
+            position,
+            racing_number: racing_number.to_string(),
+            driver_tla: text(driver.get("Tla")),
+            team_colour: text(driver.get("TeamColour")),
+            gap_to_leader,
+            interval,
+            laps: get("NumberOfLaps").and_then(Value::as_u64).unwrap_or(0) as u32,
+            last_lap: TimedValue::from_line(get("LastLapTime")),
+            best_lap: text(get("BestLapTime")),
+            sectors,
+            compound: stint
+                .and_then(|s| s.get("Compound"))
+                .and_then(|c| serde_json::from_value(c.clone()).ok()),
+            tyre_age: stint
+                .and_then(|s| s.get("TotalLaps"))
+                .and_then(Value::as_u64)
+                .map(|l| l as u32),
+            in_pit: flag("InPit"),
+            pit_out: flag("PitOut"),
+            pit_stops: get("NumberOfPitStops").and_then(Value::as_u64).unwrap_or(0) as u32,
+            retired: flag("Retired") || flag("Stopped"),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    car_data::{drs_open, CarDataSample},
+    position::PositionSample,
+};
+
+/// Most points [`LapTrace::resample`] makes, 10 cm steps over a 10 km lap.
+pub const MAX_SAMPLES: usize = 100_000;
+
+/// Car data aligned to a position sample, `distance` is in metres.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TelemetryPoint {
+    pub utc: DateTime<Utc>,
+    pub distance: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub speed: f64,
+    pub throttle: f64,
+    pub brake: bool,
+    pub gear: u8,
+    pub rpm: f64,
+    pub drs: bool,
+}
+
+/// Telemetry of a single lap, `distance` starts at 0 at the start of the lap.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LapTrace {
+    pub lap_number: u32,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    pub points: Vec<TelemetryPoint>,
+}
+
+/// Aligns the car data of one car to its position samples.
+///
+/// `CarData.z` and `Position.z` are both sent at ~4 Hz but on their own clock, so the car
+/// channels are interpolated at every position timestamp: linearly for speed, throttle and rpm,
+/// the previous value for gear, brake and DRS. Distance is integrated from the speed.
+pub fn merge(car_data: &[CarDataSample], positions: &[PositionSample]) -> Vec<TelemetryPoint> {
+    let mut car_data = car_data.to_vec();
+    car_data.sort_by_key(|s| s.utc);
+    let mut positions = positions.to_vec();
+    positions.sort_by_key(|s| s.utc);
+    if car_data.is_empty() {
+        return Vec::new();
+    }
+
+    let mut points: Vec<TelemetryPoint> = Vec::with_capacity(positions.len());
+    for pos in positions {
+        let next = car_data.partition_point(|s| s.utc <= pos.utc);
+        let prev = &car_data[next.saturating_sub(1)];
+        let next = car_data.get(next).unwrap_or(prev);
+        let span = seconds(next.utc - prev.utc);
+        let f = if span > 0.0 {
+            (seconds(pos.utc - prev.utc) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let speed = lerp(prev.speed as f64, next.speed as f64, f);
+        let distance = match points.last() {
+            Some(last) => {
+                let dt = seconds(pos.utc - last.utc);
+                last.distance + (last.speed + speed) / 2.0 / 3.6 * dt
+            }
+            None => 0.0,
+        };
+        points.push(TelemetryPoint {
+            utc: pos.utc,
+            distance,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            speed,
+            throttle: lerp(prev.throttle as f64, next.throttle as f64, f),
+            brake: prev.brake > 0,
+            gear: prev.gear,
+            rpm: lerp(prev.rpm as f64, next.rpm as f64, f),
+            drs: drs_open(prev.drs),
+        });
+    }
+    points
+}
+
+/// Splits merged telemetry into laps, `lap_starts[i]` being the start of lap `i + 1`.
+/// The last lap runs until the end of the telemetry.
+pub fn split_laps(points: &[TelemetryPoint], lap_starts: &[DateTime<Utc>]) -> Vec<LapTrace> {
+    let mut laps = Vec::new();
+    for (i, &start) in lap_starts.iter().enumerate() {
+        let end = lap_starts.get(i + 1).copied();
+        let offset = distance_at(points, start);
+        let lap_points: Vec<TelemetryPoint> = points
+            .iter()
+            .filter(|p| p.utc >= start && end.is_none_or(|end| p.utc < end))
+            .map(|p| TelemetryPoint {
+                distance: p.distance - offset,
+                ..p.clone()
+            })
+            .collect();
+        laps.push(LapTrace {
+            lap_number: i as u32 + 1,
+            start,
+            end,
+            points: lap_points,
+        });
+    }
+    laps
+}
+
+impl LapTrace {
+    /// Telemetry interpolated at `distance` metres into the lap.
+    pub fn at_distance(&self, distance: f64) -> Option<TelemetryPoint> {
+        let next = self.points.partition_point(|p| p.distance < distance);
+        let b = self.points.get(next)?;
+        if next == 0 {
+            return (b.distance == distance).then(|| b.clone());
+        }
+        let a = &self.points[next - 1];
+        let f = (distance - a.distance) / (b.distance - a.distance);
+        let millis = (b.utc - a.utc).num_milliseconds() as f64 * f;
+        Some(TelemetryPoint {
+            utc: a.utc + Duration::milliseconds(millis.round() as i64),
+            distance,
+            x: lerp(a.x, b.x, f),
+            y: lerp(a.y, b.y, f),
+            z: lerp(a.z, b.z, f),
+            speed: lerp(a.speed, b.speed, f),
+            throttle: lerp(a.throttle, b.throttle, f),
+            brake: a.brake,
+            gear: a.gear,
+            rpm: lerp(a.rpm, b.rpm, f),
+            drs: a.drs,
+        })
+    }
+
+    /// The lap resampled every `step` metres, so laps of different drivers line up point by point.
+    /// Empty unless `step` is positive and makes at most [`MAX_SAMPLES`] points.
+    pub fn resample(&self, step: f64) -> Vec<TelemetryPoint> {
+        let Some(last) = self.points.last() else {
+            return Vec::new();
+        };
+        if step.is_nan() || step <= 0.0 {
+            return Vec::new();
+        }
+        let first = (self.points[0].distance.max(0.0) / step).ceil() * step;
+        let count = ((last.distance - first) / step).floor() + 1.0;
+        if !(1.0..=MAX_SAMPLES as f64).contains(&count) {
+            return Vec::new();
+        }
+        (0..count as usize)
+            .filter_map(|i| self.at_distance(first + i as f64 * step))
+            .collect()
+    }
+
+    /// Time gained (negative) or lost (positive) in seconds against `reference`, every `step` metres.
+    pub fn time_delta(&self, reference: &LapTrace, step: f64) -> Vec<(f64, f64)> {
+        self.resample(step)
+            .into_iter()
+            .filter_map(|p| {
+                let r = reference.at_distance(p.distance)?;
+                Some((
+                    p.distance,
+                    seconds(p.utc - self.start) - seconds(r.utc - reference.start),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Cumulative distance at `utc`, interpolated between the surrounding points.
+fn distance_at(points: &[TelemetryPoint], utc: DateTime<Utc>) -> f64 {
+    let next = points.partition_point(|p| p.utc <= utc);
+    match (next.checked_sub(1).map(|i| &points[i]), points.get(next)) {
+        (Some(a), Some(b)) => {
+            let f = seconds(utc - a.utc) / seconds(b.utc - a.utc);
+            lerp(a.distance, b.distance, f)
+        }
+        (Some(a), None) => a.distance,
+        (None, Some(b)) => b.distance,
+        (None, None) => 0.0,
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.num_milliseconds() as f64 / 1000.0
+}
+
+fn lerp(a: f64, b: f64, f: f64) -> f64 {
+    a + (b - a) * f
+}
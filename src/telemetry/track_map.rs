@@ -0,0 +1,241 @@
+use std::{
+    error::Error,
+    f64::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::position::{PositionSample, PositionStatus};
+
+/// Distance in metres between two points of the resampled outline.
+const STEP: f64 = 10.0;
+/// Number of outline points the curvature is averaged over.
+const SMOOTHING: usize = 5;
+/// Curvature (rad/m) above which the track counts as turning, a radius of ~250 m.
+const CORNER_CURVATURE: f64 = 0.004;
+/// Minimum heading change for a bend to count as a corner, filters out kinks.
+const CORNER_MIN_ANGLE: f64 = 20.0 * PI / 180.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    /// Metres along the lap, measured from the start/finish line.
+    pub distance: f64,
+    /// Metres, in the coordinate system of `Position.z`.
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CornerDirection {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Corner {
+    pub number: u32,
+    pub direction: CornerDirection,
+    pub apex: TrackPoint,
+    /// Total heading change through the corner, in degrees.
+    pub angle: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarshalSector {
+    pub number: u32,
+    pub start_distance: f64,
+    pub end_distance: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrackMap {
+    /// Usually the meeting location, e.g. `Silverstone`, used as file name.
+    pub circuit: String,
+    /// Lap length in metres.
+    pub length: f64,
+    pub start_finish: TrackPoint,
+    pub outline: Vec<TrackPoint>,
+    pub corners: Vec<Corner>,
+    /// One sector around every corner, bounded halfway between apexes.
+    pub marshal_sectors: Vec<MarshalSector>,
+}
+
+impl TrackMap {
+    /// Builds the map from the `Position.z` samples of a single reference lap, which should
+    /// start and end on the start/finish line.
+    pub fn from_positions(circuit: &str, lap: &[PositionSample]) -> Result<Self, Box<dyn Error>> {
+        let mut raw: Vec<(f64, f64)> = Vec::new();
+        for sample in lap.iter().filter(|s| s.status == PositionStatus::OnTrack) {
+            let point = (sample.x / 10.0, sample.y / 10.0);
+            if raw.last() != Some(&point) {
+                raw.push(point);
+            }
+        }
+        if raw.len() < 3 {
+            return Err("Not enough on track position samples to build a track map.".into());
+        }
+        let outline = resample(&raw);
+        let length = outline.last().map(|p| p.distance).unwrap_or_default();
+        let corners = find_corners(&outline);
+        let marshal_sectors = marshal_sectors(&corners, length);
+        Ok(TrackMap {
+            circuit: circuit.to_string(),
+            length,
+            start_finish: outline[0],
+            outline,
+            corners,
+            marshal_sectors,
+        })
+    }
+
+    /// The corner the given distance into the lap belongs to, by marshal sector.
+    pub fn corner_at(&self, distance: f64) -> Option<&Corner> {
+        let sector = self
+            .marshal_sectors
+            .iter()
+            .find(|s| s.start_distance <= distance && distance < s.end_distance)?;
+        self.corners.iter().find(|c| c.number == sector.number)
+    }
+
+    pub fn path(dir: &Path, circuit: &str) -> PathBuf {
+        dir.join(format!("{}.json", circuit.to_lowercase().replace(' ', "_")))
+    }
+
+    /// Writes the map to `<dir>/<circuit>.json`.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let path = Self::path(dir, &self.circuit);
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(dir: &Path, circuit: &str) -> Result<Self, Box<dyn Error>> {
+        let json = fs::read_to_string(Self::path(dir, circuit))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Resamples the raw polyline every `STEP` metres.
+fn resample(raw: &[(f64, f64)]) -> Vec<TrackPoint> {
+    let mut outline = vec![TrackPoint {
+        distance: 0.0,
+        x: raw[0].0,
+        y: raw[0].1,
+    }];
+    let mut travelled = 0.0;
+    let mut next = STEP;
+    for pair in raw.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        let segment = (x1 - x0).hypot(y1 - y0);
+        while next <= travelled + segment {
+            let f = (next - travelled) / segment;
+            outline.push(TrackPoint {
+                distance: next,
+                x: x0 + (x1 - x0) * f,
+                y: y0 + (y1 - y0) * f,
+            });
+            next += STEP;
+        }
+        travelled += segment;
+    }
+    let (x, y) = raw[raw.len() - 1];
+    if travelled > outline[outline.len() - 1].distance {
+        outline.push(TrackPoint {
+            distance: travelled,
+            x,
+            y,
+        });
+    }
+    outline
+}
+
+/// Signed heading change per metre at every outline point, positive turning left.
+fn curvature(outline: &[TrackPoint]) -> Vec<f64> {
+    let heading: Vec<f64> = outline
+        .windows(2)
+        .map(|w| (w[1].y - w[0].y).atan2(w[1].x - w[0].x))
+        .collect();
+    let mut raw = vec![0.0; outline.len()];
+    for i in 1..heading.len() {
+        let mut change = heading[i] - heading[i - 1];
+        while change > PI {
+            change -= 2.0 * PI;
+        }
+        while change < -PI {
+            change += 2.0 * PI;
+        }
+        raw[i] = change / (outline[i].distance - outline[i - 1].distance).max(1e-6);
+    }
+    (0..raw.len())
+        .map(|i| {
+            let from = i.saturating_sub(SMOOTHING / 2);
+            let to = (i + SMOOTHING / 2 + 1).min(raw.len());
+            raw[from..to].iter().sum::<f64>() / (to - from) as f64
+        })
+        .collect()
+}
+
+fn find_corners(outline: &[TrackPoint]) -> Vec<Corner> {
+    let curvature = curvature(outline);
+    let mut corners = Vec::new();
+    let mut i = 0;
+    while i < curvature.len() {
+        if curvature[i].abs() < CORNER_CURVATURE {
+            i += 1;
+            continue;
+        }
+        // A corner lasts while the track keeps turning the same way.
+        let sign = curvature[i].signum();
+        let start = i;
+        while i < curvature.len() && curvature[i] * sign >= CORNER_CURVATURE {
+            i += 1;
+        }
+        let turned: Vec<f64> = (start..i)
+            .scan(0.0, |total, j| {
+                *total +=
+                    curvature[j] * (outline[j].distance - outline[j.saturating_sub(1)].distance);
+                Some(*total)
+            })
+            .collect();
+        let angle = turned.last().copied().unwrap_or_default();
+        // The geometric apex, halfway through the change of direction.
+        let apex = start
+            + turned
+                .iter()
+                .position(|t| t.abs() >= angle.abs() / 2.0)
+                .unwrap_or(0);
+        if angle.abs() >= CORNER_MIN_ANGLE {
+            corners.push(Corner {
+                number: corners.len() as u32 + 1,
+                direction: if sign > 0.0 {
+                    CornerDirection::Left
+                } else {
+                    CornerDirection::Right
+                },
+                apex: outline[apex],
+                angle: angle.abs().to_degrees(),
+            });
+        }
+    }
+    corners
+}
+
+fn marshal_sectors(corners: &[Corner], length: f64) -> Vec<MarshalSector> {
+    corners
+        .iter()
+        .enumerate()
+        .map(|(i, corner)| MarshalSector {
+            number: corner.number,
+            start_distance: match i {
+                0 => 0.0,
+                _ => (corners[i - 1].apex.distance + corner.apex.distance) / 2.0,
+            },
+            end_distance: match corners.get(i + 1) {
+                Some(next) => (corner.apex.distance + next.apex.distance) / 2.0,
+                None => length,
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,183 @@
+use std::error::Error;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::common::{DataStream, StreamMessage};
+use crate::schedule::Timetable;
+
+/// The `WeatherData` topic sends every value as a string, e.g. `"23.4"`.
+fn number<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Str(String),
+        Num(f64),
+    }
+    match Number::deserialize(deserializer)? {
+        Number::Str(s) => s.trim().parse().map_err(serde::de::Error::custom),
+        Number::Num(n) => Ok(n),
+    }
+}
+
+/// One `WeatherData` sample, sent about every minute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeatherSample {
+    pub utc: DateTime<Utc>,
+    /// °C
+    pub air_temp: f64,
+    /// °C
+    pub track_temp: f64,
+    /// %
+    pub humidity: f64,
+    /// mbar
+    pub pressure: f64,
+    pub rainfall: f64,
+    /// Degrees, 0 is north.
+    pub wind_direction: f64,
+    /// m/s
+    pub wind_speed: f64,
+}
+
+#[derive(Deserialize)]
+struct RawWeatherData {
+    #[serde(rename = "AirTemp", deserialize_with = "number")]
+    air_temp: f64,
+    #[serde(rename = "TrackTemp", deserialize_with = "number")]
+    track_temp: f64,
+    #[serde(rename = "Humidity", deserialize_with = "number")]
+    humidity: f64,
+    #[serde(rename = "Pressure", deserialize_with = "number")]
+    pressure: f64,
+    #[serde(rename = "Rainfall", deserialize_with = "number")]
+    rainfall: f64,
+    #[serde(rename = "WindDirection", deserialize_with = "number")]
+    wind_direction: f64,
+    #[serde(rename = "WindSpeed", deserialize_with = "number")]
+    wind_speed: f64,
+}
+
+impl WeatherSample {
+    pub fn raining(&self) -> bool {
+        self.rainfall > 0.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Stats {
+    fn of(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let (mut min, mut max, mut sum, mut n) = (f64::MAX, f64::MIN, 0.0, 0);
+        for v in values {
+            min = min.min(v);
+            max = max.max(v);
+            sum += v;
+            n += 1;
+        }
+        (n > 0).then(|| Stats {
+            min,
+            max,
+            mean: sum / n as f64,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeatherSummary {
+    pub samples: usize,
+    pub air_temp: Stats,
+    pub track_temp: Stats,
+    pub humidity: Stats,
+    pub pressure: Stats,
+    pub wind_speed: Stats,
+    /// From the first wet sample until the first dry one after it.
+    pub rain_periods: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct WeatherSeries {
+    pub samples: Vec<WeatherSample>,
+}
+
+impl WeatherSeries {
+    pub fn from_stream(stream: &mut impl DataStream) -> Result<Self, Box<dyn Error>> {
+        let mut series = WeatherSeries::default();
+        while let Some(message) = stream.next_message() {
+            series.push(&message?)?;
+        }
+        Ok(series)
+    }
+
+    pub fn push(&mut self, message: &StreamMessage) -> Result<bool, Box<dyn Error>> {
+        if message.topic_name() != "WeatherData" {
+            return Ok(false);
+        }
+        let raw: RawWeatherData = serde_json::from_value(message.payload()?)?;
+        self.samples.push(WeatherSample {
+            utc: message.timestamp,
+            air_temp: raw.air_temp,
+            track_temp: raw.track_temp,
+            humidity: raw.humidity,
+            pressure: raw.pressure,
+            rainfall: raw.rainfall,
+            wind_direction: raw.wind_direction,
+            wind_speed: raw.wind_speed,
+        });
+        Ok(true)
+    }
+
+    /// Samples in `[from, to]`.
+    pub fn between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> &[WeatherSample] {
+        let start = self.samples.partition_point(|s| s.utc < from);
+        let end = self.samples.partition_point(|s| s.utc <= to);
+        &self.samples[start..end.max(start)]
+    }
+
+    pub fn summary(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Option<WeatherSummary> {
+        let samples = self.between(from, to);
+        let stats = |f: fn(&WeatherSample) -> f64| Stats::of(samples.iter().map(f));
+        let mut rain_periods = Vec::new();
+        let mut wet_since = None;
+        for sample in samples {
+            match (wet_since, sample.raining()) {
+                (None, true) => wet_since = Some(sample.utc),
+                (Some(since), false) => {
+                    rain_periods.push((since, sample.utc));
+                    wet_since = None;
+                }
+                _ => {}
+            }
+        }
+        if let (Some(since), Some(last)) = (wet_since, samples.last()) {
+            rain_periods.push((since, last.utc));
+        }
+        Some(WeatherSummary {
+            samples: samples.len(),
+            air_temp: stats(|s| s.air_temp)?,
+            track_temp: stats(|s| s.track_temp)?,
+            humidity: stats(|s| s.humidity)?,
+            pressure: stats(|s| s.pressure)?,
+            wind_speed: stats(|s| s.wind_speed)?,
+            rain_periods,
+        })
+    }
+
+    /// Summary of every session of a timetable, `None` where there is no weather data.
+    pub fn session_summaries<'a>(
+        &self,
+        timetables: &'a [Timetable],
+    ) -> Vec<(&'a Timetable, Option<WeatherSummary>)> {
+        timetables
+            .iter()
+            .map(|t| (t, self.summary(t.start_utc(), t.end_utc())))
+            .collect()
+    }
+}
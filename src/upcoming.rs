@@ -0,0 +1,268 @@
+//! What's on now and next, from the calendar or from a weekend's timetables. Times are compared
+//! in UTC, see [`Timetable::start_utc`], `now` is usually [`crate::clock::Clock::now`].
+
+use std::error::Error;
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    schedule::{CurrentWeekend, Event, Timetable},
+    source::{DataSource, Formula1},
+    weekend_common::{Sessions, WeekendFormat},
+};
+
+/// Where a weekend is at, by the last session that started. Sprint weekends have the sprint
+/// shootout and the sprint as [`WeekendPhase::Sprint`], testing is one phase.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekendPhase {
+    PreWeekend,
+    Practice,
+    Qualifying,
+    Sprint,
+    Race,
+    PostWeekend,
+    Testing,
+}
+
+/// A session of the calendar with its event.
+#[derive(Debug, Clone)]
+pub struct ScheduledSession {
+    pub event: Event,
+    pub timetable: Timetable,
+}
+
+/// The session starting next.
+pub fn next_in(timetables: &[Timetable], now: DateTime<Utc>) -> Option<&Timetable> {
+    timetables
+        .iter()
+        .filter(|t| t.start_utc() > now)
+        .min_by_key(|t| t.start_utc())
+}
+
+/// The session running now.
+pub fn live_in(timetables: &[Timetable], now: DateTime<Utc>) -> Option<&Timetable> {
+    timetables
+        .iter()
+        .find(|t| t.start_utc() <= now && now < t.end_utc())
+}
+
+/// The session that ended last.
+pub fn previous_in(timetables: &[Timetable], now: DateTime<Utc>) -> Option<&Timetable> {
+    timetables
+        .iter()
+        .filter(|t| t.end_utc() <= now)
+        .max_by_key(|t| t.end_utc())
+}
+
+/// Time until a session starts, negative once it has.
+pub fn countdown_to(session: &Timetable, now: DateTime<Utc>) -> Duration {
+    session.start_utc() - now
+}
+
+/// Testing has practice sessions only.
+pub fn is_testing(timetables: &[Timetable]) -> bool {
+    WeekendFormat::detect(timetables) == Some(WeekendFormat::Testing)
+}
+
+pub fn phase_in(timetables: &[Timetable], now: DateTime<Utc>) -> WeekendPhase {
+    let Some(first) = timetables.iter().map(Timetable::start_utc).min() else {
+        return WeekendPhase::PreWeekend;
+    };
+    let last = timetables
+        .iter()
+        .map(Timetable::end_utc)
+        .max()
+        .unwrap_or(first);
+    if now < first {
+        return WeekendPhase::PreWeekend;
+    }
+    if now >= last {
+        return WeekendPhase::PostWeekend;
+    }
+    if is_testing(timetables) {
+        return WeekendPhase::Testing;
+    }
+    let started = timetables
+        .iter()
+        .filter(|t| t.start_utc() <= now)
+        .max_by_key(|t| t.start_utc())
+        .map(|t| t.session);
+    match started {
+        Some(Sessions::FreePractice1 | Sessions::FreePractice2 | Sessions::FreePractice3) => {
+            WeekendPhase::Practice
+        }
+        Some(Sessions::Qualifying) => WeekendPhase::Qualifying,
+        Some(Sessions::SprintShootout | Sessions::Sprint) => WeekendPhase::Sprint,
+        Some(Sessions::Race) => WeekendPhase::Race,
+        None => WeekendPhase::PreWeekend,
+    }
+}
+
+/// Event dates are local at the track, this is enough slack for any offset.
+fn slack() -> Duration {
+    Duration::days(1)
+}
+
+/// Sessions of an event, in order.
+pub fn sessions_of(event: &Event) -> Result<Vec<ScheduledSession>, Box<dyn Error>> {
+    sessions_from(&Formula1::new(), event)
+}
+
+fn sessions_from(
+    source: &dyn DataSource,
+    event: &Event,
+) -> Result<Vec<ScheduledSession>, Box<dyn Error>> {
+    let mut sessions: Vec<ScheduledSession> = source
+        .timetables(&event.meeting_key)?
+        .into_iter()
+        .map(|timetable| ScheduledSession {
+            event: event.clone(),
+            timetable,
+        })
+        .collect();
+    sessions.sort_by_key(|s| s.timetable.start_utc());
+    Ok(sessions)
+}
+
+/// Events of `year` by start. Only a failing calendar of `now`'s year is an error, the next
+/// season's isn't published until late in the year.
+fn calendar(
+    source: &dyn DataSource,
+    year: i32,
+    now: DateTime<Utc>,
+) -> Result<Vec<Event>, Box<dyn Error>> {
+    match source.calendar(&year.to_string()) {
+        Ok(mut events) => {
+            events.sort_by_key(|e| e.meeting_start_date);
+            Ok(events)
+        }
+        Err(_) if year != now.year() => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The next session of the calendar, looking into next season once this one is over.
+pub fn next_session(now: DateTime<Utc>) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    next_session_from(&Formula1::new(), now)
+}
+
+/// [`next_session`] from `source`. Events without a readable timetable (like a cancelled
+/// round) are skipped.
+pub fn next_session_from(
+    source: &dyn DataSource,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    for year in [now.year(), now.year() + 1] {
+        for event in calendar(source, year, now)? {
+            if event.meeting_end_date + slack() < now {
+                continue;
+            }
+            let Ok(sessions) = sessions_from(source, &event) else {
+                continue;
+            };
+            let next = sessions.into_iter().find(|s| s.timetable.start_utc() > now);
+            if next.is_some() {
+                return Ok(next);
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub fn live_session(now: DateTime<Utc>) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    live_session_from(&Formula1::new(), now)
+}
+
+/// [`live_session`] from `source`, events without a readable timetable are skipped.
+pub fn live_session_from(
+    source: &dyn DataSource,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    for event in calendar(source, now.year(), now)? {
+        if event.meeting_start_date - slack() <= now && now <= event.meeting_end_date + slack() {
+            let Ok(sessions) = sessions_from(source, &event) else {
+                continue;
+            };
+            let live = sessions
+                .into_iter()
+                .find(|s| s.timetable.start_utc() <= now && now < s.timetable.end_utc());
+            if live.is_some() {
+                return Ok(live);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The last session that ended, looking into last season early in the year.
+pub fn previous_session(now: DateTime<Utc>) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    previous_session_from(&Formula1::new(), now)
+}
+
+/// [`previous_session`] from `source`, events without a readable timetable are skipped.
+pub fn previous_session_from(
+    source: &dyn DataSource,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledSession>, Box<dyn Error>> {
+    for year in [now.year(), now.year() - 1] {
+        for event in calendar(source, year, now)?.into_iter().rev() {
+            if event.meeting_start_date - slack() > now {
+                continue;
+            }
+            let Ok(sessions) = sessions_from(source, &event) else {
+                continue;
+            };
+            let previous = sessions
+                .into_iter()
+                .rev()
+                .find(|s| s.timetable.end_utc() <= now);
+            if previous.is_some() {
+                return Ok(previous);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The weekend on at `now`, or the next one. Unlike [`crate::schedule::get_current_weekend`],
+/// which asks formula1.com about the real now, this works for any time, like one of a
+/// [`crate::clock::ManualClock`].
+pub fn weekend_at(now: DateTime<Utc>) -> Result<Option<CurrentWeekend>, Box<dyn Error>> {
+    weekend_at_from(&Formula1::new(), now)
+}
+
+/// [`weekend_at`] from `source`, events without a readable timetable are skipped.
+pub fn weekend_at_from(
+    source: &dyn DataSource,
+    now: DateTime<Utc>,
+) -> Result<Option<CurrentWeekend>, Box<dyn Error>> {
+    for year in [now.year(), now.year() + 1] {
+        for event in calendar(source, year, now)? {
+            if event.meeting_end_date + slack() < now {
+                continue;
+            }
+            let Ok(timetables) = source.timetables(&event.meeting_key) else {
+                continue;
+            };
+            if timetables.iter().all(|t| t.end_utc() <= now) {
+                continue;
+            }
+            return Ok(Some(CurrentWeekend {
+                season_year: year.to_string(),
+                current_or_next_meeting_key: event.meeting_key,
+                timetables,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Phase of the weekend at `now`, see [`weekend_at`].
+pub fn weekend_phase(now: DateTime<Utc>) -> Result<WeekendPhase, Box<dyn Error>> {
+    Ok(match weekend_at(now)? {
+        Some(weekend) => weekend.phase(now),
+        None => WeekendPhase::PostWeekend,
+    })
+}
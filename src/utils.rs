@@ -1,17 +1,108 @@
-use std::{error::Error, io::Read};
+use std::{
+    error::Error,
+    fs,
+    io::Read,
+    path::PathBuf,
+    sync::RwLock,
+    time::{Duration, SystemTime},
+};
 
 use reqwest;
 
 use crate::API_KEY;
 
+struct Cache {
+    dir: PathBuf,
+    max_age: Duration,
+}
+
+static CACHE: RwLock<Option<Cache>> = RwLock::new(None);
+
+/// Keeps api responses in `dir` and reuses them while younger than `max_age`.
+pub fn enable_cache(dir: impl Into<PathBuf>, max_age: Duration) -> Result<(), Box<dyn Error>> {
+    let dir = dir.into();
+    fs::create_dir_all(&dir)?;
+    *CACHE.write().map_err(|e| e.to_string())? = Some(Cache { dir, max_age });
+    Ok(())
+}
+
+pub fn disable_cache() {
+    if let Ok(mut cache) = CACHE.write() {
+        *cache = None;
+    }
+}
+
+/// File in the cache dir for `url`, `None` when caching is disabled.
+pub(crate) fn cache_file(url: &str) -> Option<(PathBuf, Duration)> {
+    let cache = CACHE.read().ok()?;
+    let cache = cache.as_ref()?;
+    let name: String = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    Some((cache.dir.join(format!("{name}.json")), cache.max_age))
+}
+
 pub fn get_from_api(url: &String) -> Result<String, Box<dyn Error>> {
-    let client = reqwest::blocking::Client::new();
-    let mut response = client
-        .get(url)
-        .header("apikey", API_KEY)
-        .header("locale", "en")
-        .send()?;
-    let mut buf = String::new();
-    response.read_to_string(&mut buf).unwrap();
+    get_cached(url, || {
+        let client = reqwest::blocking::Client::new();
+        let mut response = client
+            .get(url)
+            .header("apikey", API_KEY)
+            .header("locale", "en")
+            .send()?;
+        let success = response.status().is_success();
+        let mut buf = String::new();
+        response.read_to_string(&mut buf).unwrap();
+        Ok((success, buf))
+    })
+}
+
+/// Plain GET of a text (json) response through the cache, for apis other than formula1.com.
+pub fn get_text(url: &str) -> Result<String, Box<dyn Error>> {
+    get_cached(url, || {
+        let response = reqwest::blocking::get(url)?.error_for_status()?;
+        Ok((true, response.text()?))
+    })
+}
+
+/// Answers from the cache when enabled and fresh, otherwise `fetch`es and caches successful
+/// responses.
+fn get_cached(
+    url: &str,
+    fetch: impl FnOnce() -> Result<(bool, String), Box<dyn Error>>,
+) -> Result<String, Box<dyn Error>> {
+    let cached = cache_file(url);
+    if let Some((file, max_age)) = &cached {
+        let age = fs::metadata(file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        if age.is_some_and(|age| age <= *max_age) {
+            return Ok(fs::read_to_string(file)?);
+        }
+    }
+    let (success, buf) = fetch()?;
+    if let (Some((file, _)), true) = (cached, success) {
+        fs::write(file, &buf)?;
+    }
     Ok(buf)
 }
+
+/// Plain GET without the formula1.com api headers, e.g. for the live timing archive.
+pub fn get_bytes(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let response = reqwest::blocking::get(url)?.error_for_status()?;
+    Ok(response.bytes()?.to_vec())
+}
+
+/// `1:30.123` (or `30.123`) to milliseconds.
+pub fn parse_lap_time(time: &str) -> Option<u64> {
+    let (minutes, seconds) = match time.split_once(':') {
+        Some((minutes, seconds)) => (minutes.parse::<u64>().ok()?, seconds),
+        None => (0, time),
+    };
+    let seconds: f64 = seconds.parse().ok()?;
+    Some(minutes * 60_000 + (seconds * 1000.0).round() as u64)
+}
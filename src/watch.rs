@@ -0,0 +1,289 @@
+//! Watches a season's schedule for changes: events added or cancelled, sessions moved (weather,
+//! postponements) and status changes. Polls of formula1.com go through
+//! [`crate::utils::get_from_api`], a cache enabled with [`crate::utils::enable_cache`] should
+//! expire faster than the poll interval.
+
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    schedule::{Event, Timetable},
+    source::{DataSource, Formula1},
+    weekend_common::{Sessions, Status},
+};
+
+/// The calendar of a season and the timetables of its events.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleSnapshot {
+    pub events: Vec<Event>,
+    /// By meeting key.
+    pub timetables: HashMap<String, Vec<Timetable>>,
+}
+
+impl ScheduleSnapshot {
+    /// Timetables of events that aren't completed are fetched, the others are taken from
+    /// `previous` when it has them. An event whose timetable can't be fetched is kept without
+    /// one, its sessions aren't compared this time.
+    pub fn fetch(
+        year: &str,
+        previous: Option<&ScheduleSnapshot>,
+    ) -> Result<ScheduleSnapshot, Box<dyn Error>> {
+        Self::fetch_from(&Formula1::new(), year, previous)
+    }
+
+    /// [`ScheduleSnapshot::fetch`] from `source`.
+    pub fn fetch_from(
+        source: &dyn DataSource,
+        year: &str,
+        previous: Option<&ScheduleSnapshot>,
+    ) -> Result<ScheduleSnapshot, Box<dyn Error>> {
+        let events = source.calendar(year)?;
+        let mut timetables = HashMap::new();
+        for event in &events {
+            let known = previous.and_then(|p| p.timetables.get(&event.meeting_key));
+            let sessions = match known {
+                Some(known) if event.status == Status::Completed => known.clone(),
+                _ => match source.timetables(&event.meeting_key) {
+                    Ok(sessions) => sessions,
+                    Err(_) => continue,
+                },
+            };
+            timetables.insert(event.meeting_key.clone(), sessions);
+        }
+        Ok(ScheduleSnapshot { events, timetables })
+    }
+}
+
+/// A difference between two snapshots. Times are UTC.
+#[derive(Debug, Clone)]
+pub enum ScheduleChange {
+    EventAdded {
+        event: Event,
+    },
+    /// Cancelled, like Imola 2023.
+    EventRemoved {
+        event: Event,
+    },
+    EventRescheduled {
+        meeting_key: String,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    },
+    EventStatusChanged {
+        meeting_key: String,
+        from: Status,
+        to: Status,
+    },
+    SessionAdded {
+        meeting_key: String,
+        session: Timetable,
+    },
+    SessionRemoved {
+        meeting_key: String,
+        session: Timetable,
+    },
+    /// Start or end moved.
+    SessionRescheduled {
+        meeting_key: String,
+        session: Sessions,
+        from: (DateTime<Utc>, DateTime<Utc>),
+        to: (DateTime<Utc>, DateTime<Utc>),
+    },
+    SessionStatusChanged {
+        meeting_key: String,
+        session: Sessions,
+        from: Status,
+        to: Status,
+    },
+}
+
+/// What changed from `old` to `new`. Sessions are only compared for events that have
+/// timetables in both.
+pub fn diff(old: &ScheduleSnapshot, new: &ScheduleSnapshot) -> Vec<ScheduleChange> {
+    let mut changes = Vec::new();
+    let find = |events: &'_ [Event], key: &str| -> Option<usize> {
+        events.iter().position(|e| e.meeting_key == key)
+    };
+    for event in &old.events {
+        if find(&new.events, &event.meeting_key).is_none() {
+            changes.push(ScheduleChange::EventRemoved {
+                event: event.clone(),
+            });
+        }
+    }
+    for event in &new.events {
+        let key = &event.meeting_key;
+        let Some(i) = find(&old.events, key) else {
+            changes.push(ScheduleChange::EventAdded {
+                event: event.clone(),
+            });
+            continue;
+        };
+        let before = &old.events[i];
+        if before.meeting_start_date != event.meeting_start_date {
+            changes.push(ScheduleChange::EventRescheduled {
+                meeting_key: key.clone(),
+                from: before.meeting_start_date,
+                to: event.meeting_start_date,
+            });
+        }
+        if before.status != event.status {
+            changes.push(ScheduleChange::EventStatusChanged {
+                meeting_key: key.clone(),
+                from: before.status,
+                to: event.status,
+            });
+        }
+        if let (Some(old), Some(new)) = (old.timetables.get(key), new.timetables.get(key)) {
+            diff_sessions(key, old, new, &mut changes);
+        }
+    }
+    changes
+}
+
+fn diff_sessions(
+    key: &str,
+    old: &[Timetable],
+    new: &[Timetable],
+    changes: &mut Vec<ScheduleChange>,
+) {
+    for session in old {
+        if !new.iter().any(|t| t.session == session.session) {
+            changes.push(ScheduleChange::SessionRemoved {
+                meeting_key: key.to_string(),
+                session: session.clone(),
+            });
+        }
+    }
+    for session in new {
+        let Some(before) = old.iter().find(|t| t.session == session.session) else {
+            changes.push(ScheduleChange::SessionAdded {
+                meeting_key: key.to_string(),
+                session: session.clone(),
+            });
+            continue;
+        };
+        let (from, to) = (
+            (before.start_utc(), before.end_utc()),
+            (session.start_utc(), session.end_utc()),
+        );
+        if from != to {
+            changes.push(ScheduleChange::SessionRescheduled {
+                meeting_key: key.to_string(),
+                session: session.session,
+                from,
+                to,
+            });
+        }
+        if before.state != session.state {
+            changes.push(ScheduleChange::SessionStatusChanged {
+                meeting_key: key.to_string(),
+                session: session.session,
+                from: before.state,
+                to: session.state,
+            });
+        }
+    }
+}
+
+pub type ChangeCallback = Box<dyn FnMut(&ScheduleChange)>;
+pub type ErrorCallback = Box<dyn FnMut(&dyn Error)>;
+
+/// Polls the schedule of a season and calls back on every change. The first poll only takes
+/// the snapshot to compare against.
+pub struct ScheduleWatcher {
+    year: String,
+    snapshot: Option<ScheduleSnapshot>,
+    callbacks: Vec<ChangeCallback>,
+    error_callbacks: Vec<ErrorCallback>,
+    clock: Box<dyn Clock>,
+    source: Box<dyn DataSource>,
+}
+
+impl ScheduleWatcher {
+    pub fn new(year: &str) -> Self {
+        ScheduleWatcher {
+            year: year.to_string(),
+            snapshot: None,
+            callbacks: Vec::new(),
+            error_callbacks: Vec::new(),
+            clock: Box::new(SystemClock),
+            source: Box::new(Formula1::new()),
+        }
+    }
+
+    /// Where the schedule is polled, formula1.com by default.
+    pub fn source(&mut self, source: impl DataSource + 'static) -> &mut Self {
+        self.source = Box::new(source);
+        self
+    }
+
+    /// What [`ScheduleWatcher::run`] waits on.
+    pub fn clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    pub fn on_change(&mut self, callback: impl FnMut(&ScheduleChange) + 'static) -> &mut Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Failed polls of [`ScheduleWatcher::run`] go here, without callbacks they're only retried.
+    pub fn on_error(&mut self, callback: impl FnMut(&dyn Error) + 'static) -> &mut Self {
+        self.error_callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub fn snapshot(&self) -> Option<&ScheduleSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Compares with the last snapshot, calls back on the changes and keeps `snapshot`.
+    pub fn apply(&mut self, snapshot: ScheduleSnapshot) -> Vec<ScheduleChange> {
+        let changes = match &self.snapshot {
+            Some(previous) => diff(previous, &snapshot),
+            None => Vec::new(),
+        };
+        for change in &changes {
+            for callback in &mut self.callbacks {
+                callback(change);
+            }
+        }
+        self.snapshot = Some(snapshot);
+        changes
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<ScheduleChange>, Box<dyn Error>> {
+        let snapshot =
+            ScheduleSnapshot::fetch_from(self.source.as_ref(), &self.year, self.snapshot.as_ref())?;
+        Ok(self.apply(snapshot))
+    }
+
+    /// Polls every `interval`, for good. A failed poll is reported and the next one compares
+    /// against the last snapshot that came through.
+    pub fn run(&mut self, interval: Duration) -> ! {
+        loop {
+            self.poll_reporting();
+            self.clock.sleep(interval);
+        }
+    }
+
+    /// Like [`ScheduleWatcher::run`], until the clock passes `until`.
+    pub fn run_until(&mut self, until: DateTime<Utc>, interval: Duration) {
+        while self.clock.now() <= until {
+            self.poll_reporting();
+            self.clock.sleep(interval);
+        }
+    }
+
+    fn poll_reporting(&mut self) {
+        if let Err(e) = self.poll() {
+            for callback in &mut self.error_callbacks {
+                callback(e.as_ref());
+            }
+        }
+    }
+}
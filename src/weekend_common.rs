@@ -1,7 +1,10 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::schedule::Timetable;
+
 // #[derive(Serialize, Deserialize, Debug)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Sessions {
     // #[serde(rename = "r")]
     Race,
@@ -19,7 +22,122 @@ pub enum Sessions {
     SprintShootout,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Sessions {
+    /// Stable snake_case name, the same as the session names of the server.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sessions::Race => "race",
+            Sessions::Sprint => "sprint",
+            Sessions::Qualifying => "qualifying",
+            Sessions::SprintShootout => "sprint_shootout",
+            Sessions::FreePractice1 => "practice_1",
+            Sessions::FreePractice2 => "practice_2",
+            Sessions::FreePractice3 => "practice_3",
+        }
+    }
+}
+
+/// How a weekend is run, told apart by the sessions of its timetable and their order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekendFormat {
+    /// Three practices, qualifying and the race.
+    Conventional,
+    /// 2021 and 2022, qualifying on Friday sets the grid of the sprint on Saturday, after the
+    /// second practice.
+    Sprint2021,
+    /// 2023, a sprint shootout on Saturday sets the grid of the sprint.
+    Sprint2023,
+    /// From 2024 on, sprint qualifying and the sprint come before qualifying.
+    Sprint2024,
+    /// Practice days only.
+    Testing,
+}
+
+impl WeekendFormat {
+    /// `None` without any sessions.
+    pub fn detect(timetables: &[Timetable]) -> Option<WeekendFormat> {
+        let start = |session| {
+            timetables
+                .iter()
+                .find(|t| t.session == session)
+                .map(Timetable::start_utc)
+        };
+        if timetables.is_empty() {
+            return None;
+        }
+        let practice_only = timetables.iter().all(|t| {
+            matches!(
+                t.session,
+                Sessions::FreePractice1 | Sessions::FreePractice2 | Sessions::FreePractice3
+            )
+        });
+        if practice_only {
+            return Some(WeekendFormat::Testing);
+        }
+        Some(
+            match (
+                start(Sessions::SprintShootout),
+                start(Sessions::Sprint),
+                start(Sessions::Qualifying),
+            ) {
+                (Some(shootout), _, Some(qualifying)) if shootout < qualifying => {
+                    WeekendFormat::Sprint2024
+                }
+                (Some(_), _, _) => WeekendFormat::Sprint2023,
+                (None, Some(_), _) => WeekendFormat::Sprint2021,
+                _ => WeekendFormat::Conventional,
+            },
+        )
+    }
+
+    /// Sessions in running order, what there is to ask [`crate::results::get_results`] for.
+    /// Sprint shootouts are left out, the crate can't read their formula1.com sheet yet.
+    pub fn sessions(&self) -> &'static [Sessions] {
+        match self {
+            WeekendFormat::Conventional => &[
+                Sessions::FreePractice1,
+                Sessions::FreePractice2,
+                Sessions::FreePractice3,
+                Sessions::Qualifying,
+                Sessions::Race,
+            ],
+            WeekendFormat::Sprint2021 => &[
+                Sessions::FreePractice1,
+                Sessions::Qualifying,
+                Sessions::FreePractice2,
+                Sessions::Sprint,
+                Sessions::Race,
+            ],
+            WeekendFormat::Sprint2023 => &[
+                Sessions::FreePractice1,
+                Sessions::Qualifying,
+                Sessions::Sprint,
+                Sessions::Race,
+            ],
+            WeekendFormat::Sprint2024 => &[
+                Sessions::FreePractice1,
+                Sessions::Sprint,
+                Sessions::Qualifying,
+                Sessions::Race,
+            ],
+            WeekendFormat::Testing => &[
+                Sessions::FreePractice1,
+                Sessions::FreePractice2,
+                Sessions::FreePractice3,
+            ],
+        }
+    }
+
+    pub fn has_sprint(&self) -> bool {
+        matches!(
+            self,
+            WeekendFormat::Sprint2021 | WeekendFormat::Sprint2023 | WeekendFormat::Sprint2024
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status {
     #[serde(rename = "completed")]
     Completed,
@@ -29,12 +147,35 @@ pub enum Status {
     Na,
 }
 
+/// The api sends local times at the track next to a `gmtOffset` like `+01:00` or `-05:00`.
+pub fn to_utc(local: DateTime<Utc>, gmt_offset: &str) -> DateTime<Utc> {
+    local - parse_gmt_offset(gmt_offset)
+}
+
+/// How far ahead of UTC a `gmtOffset` is, seconds (like OpenF1's `01:00:00`) are ignored.
+pub fn parse_gmt_offset(gmt_offset: &str) -> Duration {
+    let (sign, offset) = match gmt_offset.strip_prefix('-') {
+        Some(offset) => (-1, offset),
+        None => (1, gmt_offset.trim_start_matches('+')),
+    };
+    let mut parts = offset.split(':').map(|p| p.parse::<i64>().unwrap_or(0));
+    let hours = parts.next().unwrap_or(0);
+    let minutes = parts.next().unwrap_or(0);
+    Duration::minutes(sign * (hours * 60 + minutes))
+}
+
+/// Points are whole numbers in the model, half points (of shortened races) are rounded up, so
+/// 12.5 counts as 13 and totals can be off by a half.
+pub fn whole_points(points: f64) -> i32 {
+    points.round() as i32
+}
+
 // https://serde.rs/custom-date-format.html
 pub mod custom_date_format {
-    use chrono::{DateTime, TimeZone, Utc};
+    use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    const FORMAT: &'static str = "%Y-%m-%dT%H:%M:%S"; // 2023-07-07T12:30:00
+    const FORMAT: &str = "%Y-%m-%dT%H:%M:%S"; // 2023-07-07T12:30:00
 
     pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -49,7 +190,8 @@ pub mod custom_date_format {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        Utc.datetime_from_str(&s, FORMAT)
+        NaiveDateTime::parse_from_str(&s, FORMAT)
+            .map(|naive| Utc.from_utc_datetime(&naive))
             .map_err(serde::de::Error::custom)
     }
 }
@@ -64,15 +206,14 @@ pub mod weekend_session {
     {
         let s = match session {
             Sessions::Race => "r",
-            // Sessions::Sprint => "s", // #TODO no way to be sure atm, as hungary is coming up, which does not have a sprint race.
-            Sessions::Sprint => panic!(),
-            Sessions::SprintShootout => panic!(),
+            Sessions::Sprint => "s",
+            Sessions::SprintShootout => "ss",
             Sessions::Qualifying => "q",
             Sessions::FreePractice1 => "p1",
             Sessions::FreePractice2 => "p2",
             Sessions::FreePractice3 => "p3",
         };
-        serializer.serialize_str(&s)
+        serializer.serialize_str(s)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Sessions, D::Error>
@@ -82,13 +223,19 @@ pub mod weekend_session {
         let s = &*String::deserialize(deserializer)?;
         Ok(match s {
             "r" => Sessions::Race,
-            // "s" => Sessions::Sprint, // #TODO no way to be sure atm, as hungary is coming up, which does not have a sprint race.
-            "s" => panic!(),
+            // Whether a weekend has a sprint, and which kind, is in `WeekendFormat`.
+            "s" => Sessions::Sprint,
+            // Sprint shootout in 2023, sprint qualifying from 2024 on.
+            "ss" | "sq" => Sessions::SprintShootout,
             "q" => Sessions::Qualifying,
             "p1" => Sessions::FreePractice1,
             "p2" => Sessions::FreePractice2,
             "p3" => Sessions::FreePractice3,
-            _ => panic!(), // #TODO should not happen, maybe make a error out of this later.
+            _ => {
+                return Err(serde::de::Error::custom(format!(
+                    "Session `{s}` is not supported."
+                )))
+            }
         })
     }
 }